@@ -0,0 +1,219 @@
+// The guessing game from chapter 2, moved onto a grid. The secret is a point with whole-number
+// coordinates, and every guess is answered with its straight-line distance from the secret,
+// worked out with `Point_1<f32>::distance_from_origin`.
+use super::Point_1;
+use rand::Rng;
+
+pub struct GridGame {
+    secret: Point_1<f32>,
+    size: u32,
+    guesses: usize,
+}
+
+impl GridGame {
+    // Squared distances on a grid this size still come back exactly after a round trip
+    // through `f32`, which the solver relies on.
+    pub const MAX_SIZE: u32 = 1024;
+
+    // The grid runs from 0 to `size - 1` in both directions.
+    pub fn new(size: u32, secret: Point_1<f32>) -> GridGame {
+        if size == 0 || size > GridGame::MAX_SIZE {
+            panic!(
+                "Grid size must be between 1 and {}, got {size}.",
+                GridGame::MAX_SIZE
+            );
+        }
+        if !on_grid(&secret, size) {
+            panic!(
+                "Secret point must be on the grid, got ({}, {}).",
+                secret.x, secret.y
+            );
+        }
+
+        GridGame {
+            secret,
+            size,
+            guesses: 0,
+        }
+    }
+
+    pub fn with_rng(size: u32, rng: &mut impl Rng) -> GridGame {
+        let secret = Point_1 {
+            x: rng.gen_range(0..size) as f32,
+            y: rng.gen_range(0..size) as f32,
+        };
+        GridGame::new(size, secret)
+    }
+
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    pub fn guesses(&self) -> usize {
+        self.guesses
+    }
+
+    // The distance between the guess and the secret; 0 means the guess was right.
+    pub fn guess(&mut self, guess: &Point_1<f32>) -> f32 {
+        self.guesses += 1;
+        let offset = Point_1 {
+            x: guess.x - self.secret.x,
+            y: guess.y - self.secret.y,
+        };
+        offset.distance_from_origin()
+    }
+}
+
+fn on_grid(point: &Point_1<f32>, size: u32) -> bool {
+    let inside = |value: f32| value >= 0.0 && value < size as f32 && value.fract() == 0.0;
+    inside(point.x) && inside(point.y)
+}
+
+// The distance to a grid point is the square root of a whole number, so squaring
+// and rounding gets that whole number back.
+fn squared(distance: f32) -> i64 {
+    (distance as f64).powi(2).round() as i64
+}
+
+// The whole number whose square is `n`, if there is one.
+fn exact_root(n: i64) -> Option<i64> {
+    if n < 0 {
+        return None;
+    }
+    let root = (n as f64).sqrt().round() as i64;
+    (root * root == n).then_some(root)
+}
+
+// Every grid point at this squared distance from (0, 0).
+fn points_at(squared_distance: i64, last: i64) -> Vec<(i64, i64)> {
+    (0..=last)
+        .filter_map(|x| {
+            let y = exact_root(squared_distance - x * x)?;
+            (y <= last).then_some((x, y))
+        })
+        .collect()
+}
+
+// Finds the secret by trilateration. One distance only says the secret lies somewhere on a
+// circle, and usually that circle passes through more than one grid point, so it takes a
+// second guess to tell them apart. Two corners on the same edge are enough, though: the two
+// circles cross in a pair of points mirrored in that edge, and only one of them is on the
+// grid, so the third guess is always right. When the first circle passes through a single
+// grid point, as d² = 2 only reaches (1, 1), the second guess is that point instead.
+//
+// Returns every guess with the distance it got back.
+pub fn solve(game: &mut GridGame) -> Result<Vec<(Point_1<f32>, f32)>, String> {
+    let last = (game.size() - 1) as i64;
+    let mut history = Vec::new();
+    let no_fit = || String::from("the distances do not fit any point on the grid");
+    let mut guess = |game: &mut GridGame, (x, y): (i64, i64)| {
+        let point = Point_1 {
+            x: x as f32,
+            y: y as f32,
+        };
+        let distance = game.guess(&point);
+        history.push((point, distance));
+        distance
+    };
+
+    let d1 = guess(game, (0, 0));
+    if d1 == 0.0 {
+        return Ok(history);
+    }
+    let d1 = squared(d1);
+    let candidates = points_at(d1, last);
+    let secret = match candidates[..] {
+        [] => return Err(no_fit()),
+        [only] => only,
+        _ => {
+            let d2 = guess(game, (last, 0));
+            if d2 == 0.0 {
+                return Ok(history);
+            }
+            // With the corners at (0, 0) and (n, 0):
+            //     x² + y² = d1²  and  (x - n)² + y² = d2²
+            // so x = (d1² - d2² + n²) / 2n and y = √(d1² - x²), taking the root that is on the grid.
+            let twice_x = d1 - squared(d2) + last * last;
+            if twice_x % (2 * last) != 0 {
+                return Err(no_fit());
+            }
+            let x = twice_x / (2 * last);
+            match exact_root(d1 - x * x) {
+                Some(y) if x <= last && y <= last => (x, y),
+                _ => return Err(no_fit()),
+            }
+        }
+    };
+
+    if guess(game, secret) != 0.0 {
+        return Err(no_fit());
+    }
+    Ok(history)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(x: u32, y: u32) -> Point_1<f32> {
+        Point_1 {
+            x: x as f32,
+            y: y as f32,
+        }
+    }
+
+    #[test]
+    fn distance_is_euclidean() {
+        let mut game = GridGame::new(10, point(3, 4));
+        assert_eq!(game.guess(&point(0, 0)), 5.0);
+        assert_eq!(game.guess(&point(3, 4)), 0.0);
+        assert_eq!(game.guesses(), 2);
+    }
+
+    #[test]
+    fn solves_every_point_in_at_most_three_guesses() {
+        for x in 0..40 {
+            for y in 0..40 {
+                let mut game = GridGame::new(40, point(x, y));
+                let history = solve(&mut game).unwrap();
+                let (found, distance) = history.last().unwrap();
+                assert_eq!((found.x, found.y, *distance), (x as f32, y as f32, 0.0));
+                assert!(game.guesses() <= 3, "({x}, {y})");
+            }
+        }
+    }
+
+    #[test]
+    fn largest_grid_is_still_exact() {
+        let last = GridGame::MAX_SIZE - 1;
+        for (x, y) in [(last, last), (1, last), (last - 1, 1), (517, 1000)] {
+            let mut game = GridGame::new(GridGame::MAX_SIZE, point(x, y));
+            let history = solve(&mut game).unwrap();
+            let (found, distance) = history.last().unwrap();
+            assert_eq!((found.x, found.y, *distance), (x as f32, y as f32, 0.0));
+        }
+    }
+
+    #[test]
+    fn a_distance_only_one_point_has_takes_two_guesses() {
+        for (x, y) in [(1, 1), (2, 2), (9, 9)] {
+            let mut game = GridGame::new(10, point(x, y));
+            assert_eq!(solve(&mut game).unwrap().len(), 2, "({x}, {y})");
+        }
+        // 5² = 3² + 4², so (0, 5) shares its distance with (3, 4), (4, 3) and (5, 0).
+        let mut game = GridGame::new(10, point(0, 5));
+        assert_eq!(solve(&mut game).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn one_cell_grid() {
+        let mut game = GridGame::new(1, point(0, 0));
+        assert_eq!(solve(&mut game).unwrap().len(), 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn secret_off_the_grid() {
+        GridGame::new(10, point(10, 0));
+    }
+}
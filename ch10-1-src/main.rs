@@ -1,3 +1,5 @@
+mod grid;
+
 // Not using generic
 fn largest_i32(list: &[i32]) -> &i32 {
     let mut largest = &list[0];
@@ -192,4 +194,16 @@ fn main() {
         retweet: false,
     };
     println!("1 new tweet: {}", tweet.summarize());
+
+    // The guessing game on a grid: every answer is a distance, and three are always enough.
+    let mut game = grid::GridGame::with_rng(100, &mut rand::thread_rng());
+    match grid::solve(&mut game) {
+        Ok(history) => {
+            for (point, distance) in &history {
+                println!("Guessed ({}, {}), distance {distance}", point.x, point.y);
+            }
+            println!("Found the point in {} guesses.", game.guesses());
+        }
+        Err(e) => println!("Could not find the point: {e}"),
+    }
 }
@@ -1,3 +1,7 @@
+pub mod messages;
+
+pub use messages::Lang;
+
 pub fn add(left: u64, right: u64) -> u64 {
     left + right
 }
@@ -13,8 +17,13 @@ impl Rectangle {
     }
 }
 
+// Always English, so the tests below do not depend on the environment.
 pub fn greeting(name: &str) -> String {
-    format!("Hello {name}!")
+    greeting_in(Lang::En, name)
+}
+
+pub fn greeting_in(lang: Lang, name: &str) -> String {
+    messages::message(lang, "greeting", &[("name", &name)])
 }
 
 pub struct Guess {
@@ -63,6 +72,12 @@ mod tests {
         );
     }
 
+    #[test]
+    fn greeting_in_chinese() {
+        assert_eq!(greeting_in(Lang::ZhCn, "Carol"), "你好，Carol！");
+        assert_eq!(greeting_in(Lang::En, "Carol"), "Hello Carol!");
+    }
+
     #[test]
     #[should_panic]
     fn greater_than_100() {
@@ -0,0 +1,111 @@
+use std::env;
+use std::fmt;
+use std::str::FromStr;
+
+// The text this crate produces, looked up by id the same way as the guessing game's messages
+// in chapter 2. Placeholders such as `{name}` are filled in by `message`.
+
+// The environment variable that picks a language, shared with the guessing game so one
+// setting covers both chapters.
+pub const LANGUAGE_VAR: &str = "BOOK_LANG";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Lang {
+    #[default]
+    En,
+    ZhCn,
+}
+
+impl FromStr for Lang {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Lang, String> {
+        match s {
+            "en" => Ok(Lang::En),
+            "zh-CN" | "zh" => Ok(Lang::ZhCn),
+            other => Err(format!("unknown language `{other}`, expected en or zh-CN")),
+        }
+    }
+}
+
+impl fmt::Display for Lang {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Lang::En => write!(f, "en"),
+            Lang::ZhCn => write!(f, "zh-CN"),
+        }
+    }
+}
+
+impl Lang {
+    // An unset or unknown language quietly means English, as it does for the guessing game.
+    pub fn from_env() -> Lang {
+        env::var(LANGUAGE_VAR)
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or_default()
+    }
+}
+
+const EN: &[(&str, &str)] = &[("greeting", "Hello {name}!")];
+
+const ZH_CN: &[(&str, &str)] = &[("greeting", "你好，{name}！")];
+
+fn catalog(lang: Lang) -> &'static [(&'static str, &'static str)] {
+    match lang {
+        Lang::En => EN,
+        Lang::ZhCn => ZH_CN,
+    }
+}
+
+fn lookup(lang: Lang, id: &str) -> Option<&'static str> {
+    catalog(lang)
+        .iter()
+        .find(|(key, _)| *key == id)
+        .map(|(_, text)| *text)
+}
+
+// Falls back to English for a message that has not been translated yet, and to the id
+// itself for one that does not exist at all.
+pub fn text(lang: Lang, id: &str) -> String {
+    lookup(lang, id)
+        .or_else(|| lookup(Lang::En, id))
+        .unwrap_or(id)
+        .to_string()
+}
+
+// Looks up a message and replaces each `{name}` with its argument.
+pub fn message(lang: Lang, id: &str, args: &[(&str, &dyn fmt::Display)]) -> String {
+    let mut text = text(lang, id);
+    for (name, value) in args {
+        text = text.replace(&format!("{{{name}}}"), &value.to_string());
+    }
+    text
+}
+
+// The English message ids that `lang` has no translation for.
+pub fn untranslated(lang: Lang) -> Vec<&'static str> {
+    EN.iter()
+        .map(|(id, _)| *id)
+        .filter(|id| lookup(lang, id).is_none())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chinese_is_complete() {
+        assert_eq!(untranslated(Lang::ZhCn), Vec::<&str>::new());
+        assert_eq!(EN.len(), ZH_CN.len());
+    }
+
+    #[test]
+    fn missing_messages_fall_back() {
+        assert_eq!(text(Lang::ZhCn, "no-such-message"), "no-such-message");
+        assert_eq!("en".parse(), Ok(Lang::En));
+        assert_eq!("zh".parse(), Ok(Lang::ZhCn));
+        assert!("fr".parse::<Lang>().is_err());
+    }
+}
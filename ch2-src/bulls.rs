@@ -0,0 +1,190 @@
+use crate::game::Attempts;
+use crate::messages::{message, Lang};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::fmt;
+
+// Bulls and cows: the secret is a code of distinct digits. A bull is a right digit in the
+// right place and a cow is a right digit in the wrong place.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Score {
+    pub bulls: usize,
+    pub cows: usize,
+}
+
+// Why a guess is not a valid code. Each one keeps the guess as it was typed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CodeError {
+    NotDigits(String),
+    WrongLength(String, usize),
+    Repeats(String, u8),
+}
+
+impl CodeError {
+    pub fn message(&self, lang: Lang) -> String {
+        match self {
+            CodeError::NotDigits(code) => message(lang, "code-not-digits", &[("code", code)]),
+            CodeError::WrongLength(code, length) => message(
+                lang,
+                "code-wrong-length",
+                &[("code", code), ("length", length)],
+            ),
+            CodeError::Repeats(code, digit) => {
+                message(lang, "code-repeats", &[("code", code), ("digit", digit)])
+            }
+        }
+    }
+}
+
+impl fmt::Display for CodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message(Lang::En))
+    }
+}
+
+#[derive(Debug)]
+pub struct BullsAndCows {
+    secret: Vec<u8>,
+    history: Vec<(String, Score)>,
+    attempts: Attempts,
+}
+
+impl BullsAndCows {
+    // Draws `length` distinct digits, so codes can be up to 10 digits long.
+    pub fn with_rng(rng: &mut impl Rng, length: usize) -> Result<BullsAndCows, String> {
+        if !(1..=10).contains(&length) {
+            return Err(format!("a code has between 1 and 10 digits, got {length}"));
+        }
+        let mut digits: Vec<u8> = (0..10).collect();
+        digits.shuffle(rng);
+        digits.truncate(length);
+        Ok(BullsAndCows {
+            secret: digits,
+            history: Vec::new(),
+            attempts: Attempts::default(),
+        })
+    }
+
+    pub fn with_max_attempts(mut self, max_attempts: Option<usize>) -> BullsAndCows {
+        self.attempts = Attempts::new(max_attempts);
+        self
+    }
+
+    pub fn attempts(&self) -> Attempts {
+        self.attempts
+    }
+
+    pub fn is_lost(&self) -> bool {
+        !self.is_won() && self.attempts.exhausted()
+    }
+
+    pub fn len(&self) -> usize {
+        self.secret.len()
+    }
+
+    pub fn is_won(&self) -> bool {
+        self.history
+            .last()
+            .is_some_and(|(_, score)| score.bulls == self.secret.len())
+    }
+
+    pub fn secret(&self) -> String {
+        self.secret.iter().map(|d| char::from(b'0' + d)).collect()
+    }
+
+    // Scores a guess, or explains why it is not a valid code. Invalid guesses are not recorded.
+    pub fn submit(&mut self, guess: &str) -> Result<Score, CodeError> {
+        let digits = parse_code(guess, self.secret.len())?;
+        let bulls = digits
+            .iter()
+            .zip(&self.secret)
+            .filter(|(guess, secret)| guess == secret)
+            .count();
+        let common = digits.iter().filter(|d| self.secret.contains(d)).count();
+        let score = Score {
+            bulls,
+            cows: common - bulls,
+        };
+        self.attempts.record();
+        self.history.push((guess.trim().to_string(), score));
+        Ok(score)
+    }
+}
+
+fn parse_code(code: &str, length: usize) -> Result<Vec<u8>, CodeError> {
+    let code = code.trim();
+    let digits: Vec<u8> = code
+        .chars()
+        .map(|c| c.to_digit(10).map(|d| d as u8))
+        .collect::<Option<_>>()
+        .ok_or_else(|| CodeError::NotDigits(code.to_string()))?;
+
+    if digits.len() != length || length == 0 {
+        return Err(CodeError::WrongLength(code.to_string(), length));
+    }
+    for (i, d) in digits.iter().enumerate() {
+        if digits[..i].contains(d) {
+            return Err(CodeError::Repeats(code.to_string(), *d));
+        }
+    }
+    Ok(digits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn code(secret: &str) -> BullsAndCows {
+        BullsAndCows {
+            secret: parse_code(secret, secret.len()).unwrap(),
+            history: Vec::new(),
+            attempts: Attempts::default(),
+        }
+    }
+
+    #[test]
+    fn scores_bulls_and_cows() {
+        let mut game = code("1234");
+        assert_eq!(game.submit("1243"), Ok(Score { bulls: 2, cows: 2 }));
+        assert_eq!(game.submit("5678"), Ok(Score { bulls: 0, cows: 0 }));
+        assert_eq!(game.submit("4321"), Ok(Score { bulls: 0, cows: 4 }));
+        assert!(!game.is_won());
+        assert_eq!(game.submit("1234"), Ok(Score { bulls: 4, cows: 0 }));
+        assert!(game.is_won());
+    }
+
+    #[test]
+    fn runs_out_of_attempts() {
+        let mut game = code("12").with_max_attempts(Some(2));
+        game.submit("34").unwrap();
+        assert!(!game.is_lost());
+        game.submit("21").unwrap();
+        assert!(game.is_lost());
+        assert_eq!(game.attempts().left(), Some(0));
+    }
+
+    #[test]
+    fn rejects_bad_codes() {
+        let mut game = code("1234");
+        assert!(game.submit("123").is_err());
+        assert!(game.submit("12a4").is_err());
+        assert_eq!(
+            game.submit("1123"),
+            Err(CodeError::Repeats(String::from("1123"), 1))
+        );
+        assert_eq!(
+            game.submit("123").unwrap_err().message(Lang::ZhCn),
+            "`123` 应该有 4 位数字"
+        );
+        assert_eq!(game.attempts().used(), 0);
+    }
+
+    #[test]
+    fn random_codes_have_distinct_digits() {
+        let game = BullsAndCows::with_rng(&mut StdRng::seed_from_u64(5), 6).unwrap();
+        assert!(parse_code(&game.secret(), 6).is_ok());
+        assert_eq!(game.len(), 6);
+        assert!(BullsAndCows::with_rng(&mut StdRng::seed_from_u64(5), 11).is_err());
+    }
+}
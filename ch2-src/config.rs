@@ -0,0 +1,296 @@
+use crate::game::Number;
+use crate::leaderboard::Report;
+use crate::liar::{self, Liar};
+use crate::messages::Lang;
+use crate::settings::{Difficulty, Settings};
+use crate::timer::TimeLimits;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+// Command-line options, parsed the same way as the minigrep `Config` in the book:
+// `build` takes the argument iterator and returns an error message instead of panicking.
+#[derive(Debug, Default, PartialEq)]
+pub struct Config {
+    pub seed: Option<u64>,
+    pub record: Option<String>,
+    pub replay: Option<String>,
+    pub batch: bool,
+    pub difficulty: Option<Difficulty>,
+    pub min: Option<Number>,
+    pub max: Option<Number>,
+    pub attempts: Option<usize>,
+    pub player: Option<String>,
+    pub leaderboard: Option<String>,
+    pub show: Option<Report>,
+    pub reverse: bool,
+    pub lies: Option<usize>,
+    pub solve: bool,
+    pub liar: Option<Liar>,
+    pub tournament: Option<usize>,
+    pub csv: bool,
+    pub serve: Option<SocketAddr>,
+    pub idle: Option<u64>,
+    pub resume: Option<String>,
+    pub hints: bool,
+    pub bulls: Option<usize>,
+    pub lang: Option<Lang>,
+    pub untranslated: bool,
+    pub time_limit: Option<u64>,
+    pub total_time: Option<u64>,
+}
+
+impl Config {
+    pub fn build(mut args: impl Iterator<Item = String>) -> Result<Config, String> {
+        // The first value is the name of the program.
+        args.next();
+
+        let mut config = Config::default();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--seed" => config.seed = Some(next_number(&mut args, "--seed")?),
+                "--min" => config.min = Some(next_number(&mut args, "--min")?),
+                "--max" => config.max = Some(next_number(&mut args, "--max")?),
+                "--attempts" => config.attempts = Some(next_number(&mut args, "--attempts")?),
+                "--difficulty" => {
+                    config.difficulty = Some(next_value(&mut args, "--difficulty")?.parse()?)
+                }
+                "--record" => config.record = Some(next_value(&mut args, "--record")?),
+                "--resume" => config.resume = Some(next_value(&mut args, "--resume")?),
+                "--replay" => config.replay = Some(next_value(&mut args, "--replay")?),
+                "--batch" => config.batch = true,
+                "--hints" => config.hints = true,
+                "--bulls" => config.bulls = Some(next_number(&mut args, "--bulls")?),
+                "--reverse" => config.reverse = true,
+                "--lies" => {
+                    let lies = next_number(&mut args, "--lies")?;
+                    if lies > liar::MAX_LIES {
+                        return Err(format!(
+                            "--lies must be at most {}, got {lies}",
+                            liar::MAX_LIES
+                        ));
+                    }
+                    config.lies = Some(lies);
+                }
+                "--solve" => config.solve = true,
+                "--tournament" => config.tournament = Some(next_number(&mut args, "--tournament")?),
+                "--csv" => config.csv = true,
+                "--serve" => {
+                    let value = next_value(&mut args, "--serve")?;
+                    let addr: SocketAddr = value.parse().map_err(|_| {
+                        format!("--serve expects an address like 127.0.0.1:7878, got `{value}`")
+                    })?;
+                    // The server has no authentication, so it only ever listens on this machine.
+                    if !addr.ip().is_loopback() {
+                        return Err(format!(
+                            "--serve only accepts loopback addresses, got `{value}`"
+                        ));
+                    }
+                    config.serve = Some(addr);
+                }
+                "--time-limit" => config.time_limit = Some(next_number(&mut args, "--time-limit")?),
+                "--total-time" => config.total_time = Some(next_number(&mut args, "--total-time")?),
+                "--idle" => config.idle = Some(next_number(&mut args, "--idle")?),
+                "--lang" => config.lang = Some(next_value(&mut args, "--lang")?.parse()?),
+                "--untranslated" => config.untranslated = true,
+                "--liar" => config.liar = Some(next_value(&mut args, "--liar")?.parse()?),
+                "--player" => config.player = Some(next_value(&mut args, "--player")?),
+                "--leaderboard" => {
+                    config.leaderboard = Some(next_value(&mut args, "--leaderboard")?)
+                }
+                "--show" => config.show = Some(next_value(&mut args, "--show")?.parse()?),
+                other => return Err(format!("unknown option `{other}`")),
+            }
+        }
+
+        // A saved game brings its own range, attempts and secret, and a replay file is
+        // rebuilt from the seed, which a resumed game no longer has. The other modes are
+        // different games altogether.
+        if config.resume.is_some() {
+            let conflicts = [
+                ("--seed", config.seed.is_some()),
+                ("--record", config.record.is_some()),
+                ("--replay", config.replay.is_some()),
+                ("--difficulty", config.difficulty.is_some()),
+                ("--min", config.min.is_some()),
+                ("--max", config.max.is_some()),
+                ("--attempts", config.attempts.is_some()),
+                ("--show", config.show.is_some()),
+                ("--reverse", config.reverse),
+                ("--lies", config.lies.is_some()),
+                ("--solve", config.solve),
+                ("--liar", config.liar.is_some()),
+                ("--tournament", config.tournament.is_some()),
+                ("--csv", config.csv),
+                ("--serve", config.serve.is_some()),
+                ("--idle", config.idle.is_some()),
+                ("--bulls", config.bulls.is_some()),
+                ("--untranslated", config.untranslated),
+            ];
+            if let Some((option, _)) = conflicts.iter().find(|(_, given)| *given) {
+                return Err(format!("{option} cannot be used with --resume"));
+            }
+        }
+
+        Ok(config)
+    }
+
+    // Starts from the chosen preset (or the original 1 to 100 game) and lets
+    // `--min`, `--max` and `--attempts` override parts of it.
+    pub fn settings(&self) -> Result<Settings, String> {
+        let base = self
+            .difficulty
+            .map(Difficulty::settings)
+            .unwrap_or_default();
+        Settings::new(
+            self.min.unwrap_or(*base.range.start()),
+            self.max.unwrap_or(*base.range.end()),
+            self.attempts.or(base.max_attempts),
+        )
+    }
+
+    // The name a game is filed under on the leaderboard. Scores are only comparable
+    // when the rules match, so any override of a preset counts as "custom".
+    pub fn difficulty_label(&self) -> String {
+        let overridden = self.min.is_some() || self.max.is_some() || self.attempts.is_some();
+        match (self.difficulty, overridden) {
+            (Some(difficulty), false) => difficulty.to_string(),
+            (None, false) => String::from("classic"),
+            (_, true) => String::from("custom"),
+        }
+    }
+
+    // Both limits are given in whole seconds on the command line.
+    pub fn time_limits(&self) -> TimeLimits {
+        TimeLimits {
+            per_guess: self.time_limit.map(Duration::from_secs),
+            total: self.total_time.map(Duration::from_secs),
+        }
+    }
+}
+
+fn next_value(args: &mut impl Iterator<Item = String>, option: &str) -> Result<String, String> {
+    args.next()
+        .ok_or_else(|| format!("{option} expects a value"))
+}
+
+fn next_number<T: std::str::FromStr>(
+    args: &mut impl Iterator<Item = String>,
+    option: &str,
+) -> Result<T, String> {
+    let value = next_value(args, option)?;
+    value
+        .parse()
+        .map_err(|_| format!("{option} expects a number, got `{value}`"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build(args: &[&str]) -> Result<Config, String> {
+        Config::build(
+            std::iter::once("guessing_game")
+                .chain(args.iter().copied())
+                .map(String::from),
+        )
+    }
+
+    #[test]
+    fn parses_seed_and_record() {
+        let config = build(&["--seed", "42", "--record", "game.txt"]).unwrap();
+        assert_eq!(config.seed, Some(42));
+        assert_eq!(config.record.as_deref(), Some("game.txt"));
+    }
+
+    #[test]
+    fn options_override_the_preset() {
+        let config = build(&["--difficulty", "hard", "--min", "-500"]).unwrap();
+        let settings = config.settings().unwrap();
+        assert_eq!(settings.range, -500..=1000);
+        assert_eq!(settings.max_attempts, Some(10));
+    }
+
+    #[test]
+    fn inverted_range_is_an_error() {
+        let config = build(&["--min", "10", "--max", "1"]).unwrap();
+        assert!(config.settings().is_err());
+    }
+
+    #[test]
+    fn difficulty_label() {
+        assert_eq!(build(&[]).unwrap().difficulty_label(), "classic");
+        let hard = build(&["--difficulty", "hard"]).unwrap();
+        assert_eq!(hard.difficulty_label(), "hard");
+        let custom = build(&["--difficulty", "hard", "--attempts", "20"]).unwrap();
+        assert_eq!(custom.difficulty_label(), "custom");
+    }
+
+    #[test]
+    fn serve_is_loopback_only() {
+        assert!(build(&["--serve", "127.0.0.1:7878"]).is_ok());
+        assert!(build(&["--serve", "[::1]:7878"]).is_ok());
+        assert!(build(&["--serve", "0.0.0.0:7878"]).is_err());
+    }
+
+    #[test]
+    fn parses_hints_and_bulls() {
+        let config = build(&["--hints", "--bulls", "4"]).unwrap();
+        assert!(config.hints);
+        assert_eq!(config.bulls, Some(4));
+        assert!(build(&["--bulls", "four"]).is_err());
+    }
+
+    #[test]
+    fn parses_language() {
+        assert_eq!(build(&["--lang", "zh-CN"]).unwrap().lang, Some(Lang::ZhCn));
+        assert!(build(&["--lang", "klingon"]).is_err());
+    }
+
+    #[test]
+    fn parses_time_limits() {
+        let limits = build(&["--time-limit", "10", "--total-time", "60"])
+            .unwrap()
+            .time_limits();
+        assert_eq!(limits.per_guess, Some(Duration::from_secs(10)));
+        assert_eq!(limits.total, Some(Duration::from_secs(60)));
+        assert_eq!(build(&[]).unwrap().time_limits(), TimeLimits::default());
+    }
+
+    #[test]
+    fn caps_lies() {
+        let cap = liar::MAX_LIES.to_string();
+        assert_eq!(build(&["--lies", &cap]).unwrap().lies, Some(liar::MAX_LIES));
+        let above = (liar::MAX_LIES + 1).to_string();
+        assert!(build(&["--lies", &above]).is_err());
+        assert!(build(&["--lies", "18446744073709551615"]).is_err());
+    }
+
+    #[test]
+    fn resume_rejects_other_settings() {
+        assert!(build(&["--resume", "game.sav", "--hints", "--player", "ann"]).is_ok());
+        for option in [
+            &["--difficulty", "hard"][..],
+            &["--min", "5"],
+            &["--max", "50"],
+            &["--record", "game.txt"],
+            &["--reverse"],
+            &["--lies", "1"],
+            &["--bulls", "4"],
+        ] {
+            let args: Vec<&str> = ["--resume", "game.sav"]
+                .iter()
+                .chain(option)
+                .copied()
+                .collect();
+            let err = build(&args).unwrap_err();
+            assert_eq!(err, format!("{} cannot be used with --resume", option[0]));
+        }
+    }
+
+    #[test]
+    fn rejects_bad_seed() {
+        assert!(build(&["--seed", "forty-two"]).is_err());
+        assert!(build(&["--seed"]).is_err());
+    }
+}
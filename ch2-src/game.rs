@@ -0,0 +1,335 @@
+use crate::hints::{self, Hint};
+use rand::Rng;
+use std::cmp::Ordering;
+use std::ops::RangeInclusive;
+
+// Guesses and secrets are `i64` so a game can use negative numbers and very large ranges.
+pub type Number = i64;
+
+// The result of comparing one guess against the secret number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    TooSmall,
+    TooLarge,
+    Win,
+}
+
+// `guess.cmp(&secret_number)` already tells us everything we need,
+// so an `Outcome` is just an `Ordering` with names that fit the game.
+impl From<Ordering> for Outcome {
+    fn from(ordering: Ordering) -> Self {
+        match ordering {
+            Ordering::Less => Outcome::TooSmall,
+            Ordering::Greater => Outcome::TooLarge,
+            Ordering::Equal => Outcome::Win,
+        }
+    }
+}
+
+// Counts guesses against an optional limit. Every game mode with attempts shares this,
+// so "out of attempts" means the same thing everywhere.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Attempts {
+    max: Option<usize>,
+    used: usize,
+}
+
+impl Attempts {
+    pub fn new(max: Option<usize>) -> Attempts {
+        Attempts { max, used: 0 }
+    }
+
+    pub fn record(&mut self) {
+        self.used += 1;
+    }
+
+    pub fn used(&self) -> usize {
+        self.used
+    }
+
+    pub fn max(&self) -> Option<usize> {
+        self.max
+    }
+
+    pub fn left(&self) -> Option<usize> {
+        self.max.map(|max| max.saturating_sub(self.used))
+    }
+
+    pub fn exhausted(&self) -> bool {
+        self.left() == Some(0)
+    }
+}
+
+// The game engine: it owns the secret, the allowed range and every guess made so far,
+// but knows nothing about where guesses come from or where results go.
+#[derive(Debug)]
+pub struct GuessingGame {
+    secret: Number,
+    range: RangeInclusive<Number>,
+    history: Vec<(Number, Outcome)>,
+    attempts: Attempts,
+    hints: bool,
+}
+
+impl GuessingGame {
+    pub fn new(secret: Number, range: RangeInclusive<Number>) -> GuessingGame {
+        if !range.contains(&secret) {
+            panic!(
+                "Secret number must be between {} and {}, got {secret}.",
+                range.start(),
+                range.end()
+            );
+        }
+
+        GuessingGame {
+            secret,
+            range,
+            history: Vec::new(),
+            attempts: Attempts::default(),
+            hints: false,
+        }
+    }
+
+    // Draws the secret from any random number generator, so callers decide
+    // whether a game is unpredictable (`thread_rng`) or reproducible (a seeded `StdRng`).
+    pub fn with_rng(rng: &mut impl Rng, range: RangeInclusive<Number>) -> GuessingGame {
+        let secret = rng.gen_range(range.clone());
+        GuessingGame::new(secret, range)
+    }
+
+    // Limits how many guesses the player gets; `None` means unlimited.
+    pub fn with_max_attempts(mut self, max_attempts: Option<usize>) -> GuessingGame {
+        self.attempts = Attempts::new(max_attempts);
+        self
+    }
+
+    // Turns on hot/cold hints after every guess.
+    pub fn with_hints(mut self, hints: bool) -> GuessingGame {
+        self.hints = hints;
+        self
+    }
+
+    pub fn submit(&mut self, guess: Number) -> Outcome {
+        let outcome = Outcome::from(guess.cmp(&self.secret));
+        self.history.push((guess, outcome));
+        self.attempts.record();
+        outcome
+    }
+
+    // A turn where no guess arrived in time still uses up an attempt.
+    pub fn time_out(&mut self) {
+        self.attempts.record();
+    }
+
+    // How close the latest guess was, if hints are turned on.
+    pub fn hint(&self) -> Option<Hint> {
+        if !self.hints {
+            return None;
+        }
+        let (guess, _) = *self.history.last()?;
+        let previous = self
+            .history
+            .len()
+            .checked_sub(2)
+            .map(|index| self.history[index].0);
+        Some(hints::hint(&self.range, self.secret, guess, previous))
+    }
+
+    // Only for saving a game in progress; front ends should never show it.
+    pub fn secret(&self) -> Number {
+        self.secret
+    }
+
+    pub fn range(&self) -> &RangeInclusive<Number> {
+        &self.range
+    }
+
+    pub fn history(&self) -> &[(Number, Outcome)] {
+        &self.history
+    }
+
+    pub fn max_attempts(&self) -> Option<usize> {
+        self.attempts.max()
+    }
+
+    // Counts guesses and timed-out turns alike, so it can be larger than `history().len()`.
+    pub fn attempts_used(&self) -> usize {
+        self.attempts.used()
+    }
+
+    pub fn attempts_left(&self) -> Option<usize> {
+        self.attempts.left()
+    }
+
+    pub fn is_won(&self) -> bool {
+        self.history.last().map(|&(_, outcome)| outcome) == Some(Outcome::Win)
+    }
+
+    pub fn is_lost(&self) -> bool {
+        !self.is_won() && self.attempts_left() == Some(0)
+    }
+}
+
+// A front end supplies guesses and shows results. The terminal is one implementation;
+// a test harness or a network session can drive the same engine by implementing this trait.
+pub trait Frontend {
+    fn start(&mut self, game: &GuessingGame);
+    // Returns `None` once there is no more input, for example when stdin is closed.
+    // The game is passed along so a front end can offer commands such as saving.
+    fn read_guess(&mut self, game: &GuessingGame) -> Option<Number>;
+    fn report(&mut self, guess: Number, outcome: Outcome);
+    // Only called when the game has hints turned on; front ends without hints can ignore it.
+    fn hint(&mut self, _hint: Hint) {}
+}
+
+// How a call to `play` finished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ending {
+    Won { guesses: usize },
+    // Every allowed attempt was used without finding the secret number.
+    Lost { secret: Number },
+    // The front end ran out of input before the secret number was found.
+    Abandoned { guesses: usize },
+    // The overall clock of a timed game ran out.
+    OutOfTime { secret: Number },
+}
+
+// Runs the game until the secret number is found, the attempts run out,
+// or the front end has no more guesses.
+pub fn play(game: &mut GuessingGame, frontend: &mut impl Frontend) -> Ending {
+    frontend.start(game);
+
+    while !game.is_won() {
+        if game.is_lost() {
+            return Ending::Lost {
+                secret: game.secret,
+            };
+        }
+        let Some(guess) = frontend.read_guess(game) else {
+            return Ending::Abandoned {
+                guesses: game.history().len(),
+            };
+        };
+        turn(game, frontend, guess);
+    }
+
+    Ending::Won {
+        guesses: game.history().len(),
+    }
+}
+
+// Submits one guess and shows the result, including a hint when the game has them.
+pub fn turn(game: &mut GuessingGame, frontend: &mut impl Frontend, guess: Number) {
+    let outcome = game.submit(guess);
+    frontend.report(guess, outcome);
+    if let Some(hint) = game.hint() {
+        frontend.hint(hint);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Scripted {
+        guesses: Vec<Number>,
+        reports: Vec<(Number, Outcome)>,
+    }
+
+    impl Frontend for Scripted {
+        fn start(&mut self, _game: &GuessingGame) {}
+
+        fn read_guess(&mut self, _game: &GuessingGame) -> Option<Number> {
+            if self.guesses.is_empty() {
+                None
+            } else {
+                Some(self.guesses.remove(0))
+            }
+        }
+
+        fn report(&mut self, guess: Number, outcome: Outcome) {
+            self.reports.push((guess, outcome));
+        }
+    }
+
+    #[test]
+    fn submit_compares_against_secret() {
+        let mut game = GuessingGame::new(42, 1..=100);
+        assert_eq!(game.submit(10), Outcome::TooSmall);
+        assert_eq!(game.submit(90), Outcome::TooLarge);
+        assert!(!game.is_won());
+        assert_eq!(game.submit(42), Outcome::Win);
+        assert!(game.is_won());
+        assert_eq!(game.history().len(), 3);
+    }
+
+    #[test]
+    fn play_stops_at_the_winning_guess() {
+        let mut game = GuessingGame::new(7, 1..=10);
+        let mut frontend = Scripted {
+            guesses: vec![5, 8, 7, 1],
+            reports: Vec::new(),
+        };
+
+        assert_eq!(play(&mut game, &mut frontend), Ending::Won { guesses: 3 });
+        assert_eq!(
+            frontend.reports,
+            vec![
+                (5, Outcome::TooSmall),
+                (8, Outcome::TooLarge),
+                (7, Outcome::Win)
+            ]
+        );
+    }
+
+    #[test]
+    fn play_is_abandoned_when_input_runs_out() {
+        let mut game = GuessingGame::new(7, 1..=10);
+        let mut frontend = Scripted {
+            guesses: vec![1, 2],
+            reports: Vec::new(),
+        };
+
+        assert_eq!(
+            play(&mut game, &mut frontend),
+            Ending::Abandoned { guesses: 2 }
+        );
+    }
+
+    #[test]
+    fn play_is_lost_when_attempts_run_out() {
+        let mut game = GuessingGame::new(7, 1..=10).with_max_attempts(Some(2));
+        let mut frontend = Scripted {
+            guesses: vec![1, 2, 7],
+            reports: Vec::new(),
+        };
+
+        assert_eq!(play(&mut game, &mut frontend), Ending::Lost { secret: 7 });
+        assert_eq!(frontend.reports.len(), 2);
+    }
+
+    #[test]
+    fn wide_and_negative_ranges() {
+        let mut game = GuessingGame::new(-5, Number::MIN..=Number::MAX);
+        assert_eq!(game.submit(Number::MIN), Outcome::TooSmall);
+        assert_eq!(game.submit(Number::MAX), Outcome::TooLarge);
+        assert_eq!(game.submit(-5), Outcome::Win);
+    }
+
+    #[test]
+    fn same_seed_same_secret() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let mut first = GuessingGame::with_rng(&mut StdRng::seed_from_u64(7), 1..=100);
+        let mut second = GuessingGame::with_rng(&mut StdRng::seed_from_u64(7), 1..=100);
+        for guess in 1..=100 {
+            assert_eq!(first.submit(guess), second.submit(guess));
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn secret_outside_range() {
+        GuessingGame::new(200, 1..=100);
+    }
+}
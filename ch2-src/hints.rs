@@ -0,0 +1,83 @@
+use crate::game::Number;
+use std::ops::RangeInclusive;
+
+// How far a guess is from the secret, as a share of the whole range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Band {
+    Freezing,
+    Cold,
+    Warm,
+    Hot,
+}
+
+// Whether a guess got closer to the secret than the one before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trend {
+    Warmer,
+    Colder,
+    Same,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hint {
+    pub band: Band,
+    // `None` for the first guess, which has nothing to compare against.
+    pub trend: Option<Trend>,
+}
+
+// Distances are worked out in `i128` because two `i64` values can be further apart than `i64::MAX`.
+fn distance(a: Number, b: Number) -> i128 {
+    (a as i128 - b as i128).abs()
+}
+
+pub fn hint(
+    range: &RangeInclusive<Number>,
+    secret: Number,
+    guess: Number,
+    previous: Option<Number>,
+) -> Hint {
+    let size = distance(*range.start(), *range.end()) + 1;
+    let away = distance(guess, secret);
+
+    // Within 5% of the range is hot, 15% warm, 40% cold, and anything further is freezing.
+    let band = match away * 100 {
+        d if d <= size * 5 => Band::Hot,
+        d if d <= size * 15 => Band::Warm,
+        d if d <= size * 40 => Band::Cold,
+        _ => Band::Freezing,
+    };
+
+    let trend = previous.map(|previous| match away.cmp(&distance(previous, secret)) {
+        std::cmp::Ordering::Less => Trend::Warmer,
+        std::cmp::Ordering::Greater => Trend::Colder,
+        std::cmp::Ordering::Equal => Trend::Same,
+    });
+
+    Hint { band, trend }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bands_and_trend() {
+        let range = 1..=100;
+        assert_eq!(hint(&range, 50, 52, None).band, Band::Hot);
+        assert_eq!(hint(&range, 50, 60, None).band, Band::Warm);
+        assert_eq!(hint(&range, 50, 80, None).band, Band::Cold);
+        assert_eq!(hint(&range, 50, 100, None).band, Band::Freezing);
+
+        assert_eq!(hint(&range, 50, 60, Some(90)).trend, Some(Trend::Warmer));
+        assert_eq!(hint(&range, 50, 90, Some(60)).trend, Some(Trend::Colder));
+        assert_eq!(hint(&range, 50, 40, Some(60)).trend, Some(Trend::Same));
+    }
+
+    #[test]
+    fn full_range_does_not_overflow() {
+        let range = Number::MIN..=Number::MAX;
+        let hint = hint(&range, Number::MAX, Number::MIN, Some(0));
+        assert_eq!(hint.band, Band::Freezing);
+        assert_eq!(hint.trend, Some(Trend::Colder));
+    }
+}
@@ -0,0 +1,222 @@
+use crate::game::Number;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, OpenOptions};
+use std::io::{self, ErrorKind, Write};
+use std::str::FromStr;
+use std::time::Duration;
+
+// The statistics that `--show` can print.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Report {
+    Top,
+    Players,
+    Histogram,
+}
+
+impl FromStr for Report {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Report, String> {
+        match s {
+            "top" => Ok(Report::Top),
+            "players" => Ok(Report::Players),
+            "histogram" => Ok(Report::Histogram),
+            other => Err(format!(
+                "unknown report `{other}`, expected top, players or histogram"
+            )),
+        }
+    }
+}
+
+// One finished game. Records are stored one per line, tab separated:
+//     player  difficulty  low  high  guesses  won  duration_ms  timestamp
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    pub player: String,
+    pub difficulty: String,
+    pub low: Number,
+    pub high: Number,
+    pub guesses: usize,
+    pub won: bool,
+    pub duration: Duration,
+    // Seconds since the Unix epoch.
+    pub timestamp: u64,
+}
+
+impl Record {
+    fn to_line(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            clean(&self.player),
+            clean(&self.difficulty),
+            self.low,
+            self.high,
+            self.guesses,
+            self.won,
+            self.duration.as_millis(),
+            self.timestamp
+        )
+    }
+
+    fn from_line(line: &str) -> Option<Record> {
+        let fields: Vec<&str> = line.split('\t').collect();
+        let [player, difficulty, low, high, guesses, won, duration, timestamp] = fields[..] else {
+            return None;
+        };
+
+        Some(Record {
+            player: player.to_string(),
+            difficulty: difficulty.to_string(),
+            low: low.parse().ok()?,
+            high: high.parse().ok()?,
+            guesses: guesses.parse().ok()?,
+            won: won.parse().ok()?,
+            duration: Duration::from_millis(duration.parse().ok()?),
+            timestamp: timestamp.parse().ok()?,
+        })
+    }
+}
+
+// Tabs and newlines would break the line format, so they never make it into the file.
+fn clean(field: &str) -> String {
+    field.replace(['\t', '\n', '\r'], " ")
+}
+
+#[derive(Debug, Default)]
+pub struct Leaderboard {
+    pub records: Vec<Record>,
+    // How many lines of the file could not be understood and were skipped.
+    pub skipped: usize,
+}
+
+impl Leaderboard {
+    // A missing file is an empty leaderboard, and lines that do not parse are skipped
+    // rather than making the whole file unusable.
+    pub fn load(path: &str) -> Result<Leaderboard, io::Error> {
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Leaderboard::default()),
+            Err(e) => return Err(e),
+        };
+        Ok(Leaderboard::parse(&String::from_utf8_lossy(&bytes)))
+    }
+
+    pub fn parse(contents: &str) -> Leaderboard {
+        let mut leaderboard = Leaderboard::default();
+        for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+            match Record::from_line(line) {
+                Some(record) => leaderboard.records.push(record),
+                None => leaderboard.skipped += 1,
+            }
+        }
+        leaderboard
+    }
+
+    // Appends a single record, so an existing file is never rewritten
+    // and a crash halfway through cannot lose earlier games.
+    pub fn append(path: &str, record: &Record) -> Result<(), io::Error> {
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(file, "{}", record.to_line())
+    }
+
+    // The best won games for each difficulty: fewest guesses first, then fastest.
+    pub fn top_scores(&self, per_difficulty: usize) -> BTreeMap<&str, Vec<&Record>> {
+        let mut by_difficulty: BTreeMap<&str, Vec<&Record>> = BTreeMap::new();
+        for record in self.records.iter().filter(|record| record.won) {
+            by_difficulty
+                .entry(&record.difficulty)
+                .or_default()
+                .push(record);
+        }
+        for records in by_difficulty.values_mut() {
+            records.sort_by_key(|record| (record.guesses, record.duration));
+            records.truncate(per_difficulty);
+        }
+        by_difficulty
+    }
+
+    // Average guesses per finished game for every player, sorted by name.
+    pub fn average_guesses(&self) -> Vec<(&str, f64)> {
+        let mut totals: HashMap<&str, (usize, usize)> = HashMap::new();
+        for record in &self.records {
+            let total = totals.entry(&record.player).or_insert((0, 0));
+            total.0 += record.guesses;
+            total.1 += 1;
+        }
+
+        let mut averages: Vec<(&str, f64)> = totals
+            .into_iter()
+            .map(|(player, (guesses, games))| (player, guesses as f64 / games as f64))
+            .collect();
+        averages.sort_by(|a, b| a.0.cmp(b.0));
+        averages
+    }
+
+    // How many won games needed each number of guesses.
+    pub fn histogram(&self) -> BTreeMap<usize, usize> {
+        let mut histogram = BTreeMap::new();
+        for record in self.records.iter().filter(|record| record.won) {
+            *histogram.entry(record.guesses).or_insert(0) += 1;
+        }
+        histogram
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(player: &str, difficulty: &str, guesses: usize, won: bool) -> Record {
+        Record {
+            player: player.to_string(),
+            difficulty: difficulty.to_string(),
+            low: 1,
+            high: 100,
+            guesses,
+            won,
+            duration: Duration::from_millis(1500),
+            timestamp: 1_700_000_000,
+        }
+    }
+
+    #[test]
+    fn record_round_trips_through_a_line() {
+        let original = record("Carol", "hard", 9, true);
+        assert_eq!(Record::from_line(&original.to_line()), Some(original));
+    }
+
+    #[test]
+    fn corrupt_lines_are_skipped() {
+        let good = record("Carol", "easy", 4, true).to_line();
+        let leaderboard = Leaderboard::parse(&format!("{good}\nnot a record\n\u{0}\t1\n{good}\n"));
+        assert_eq!(leaderboard.records.len(), 2);
+        assert_eq!(leaderboard.skipped, 2);
+    }
+
+    #[test]
+    fn statistics() {
+        let leaderboard = Leaderboard {
+            records: vec![
+                record("Carol", "easy", 4, true),
+                record("Carol", "easy", 6, true),
+                record("Dave", "easy", 3, true),
+                record("Dave", "hard", 10, false),
+            ],
+            skipped: 0,
+        };
+
+        let top = leaderboard.top_scores(2);
+        let easy: Vec<usize> = top["easy"].iter().map(|record| record.guesses).collect();
+        assert_eq!(easy, vec![3, 4]);
+        assert!(!top.contains_key("hard"));
+
+        assert_eq!(
+            leaderboard.average_guesses(),
+            vec![("Carol", 5.0), ("Dave", 6.5)]
+        );
+        assert_eq!(
+            leaderboard.histogram().into_iter().collect::<Vec<_>>(),
+            vec![(3, 1), (4, 1), (6, 1)]
+        );
+    }
+}
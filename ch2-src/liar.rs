@@ -0,0 +1,339 @@
+use crate::game::{Number, Outcome};
+use rand::Rng;
+use std::ops::RangeInclusive;
+
+// Ulam's game: the secret keeper may lie in up to `k` of its "Too small"/"Too large" answers.
+// A correct guess is always answered with `Win`, so a game still ends the moment the secret is hit.
+
+// Decides which answers the oracle lies about.
+pub trait LieStrategy {
+    fn lie(&mut self, guess: Number, truth: Outcome, lies_left: usize) -> bool;
+}
+
+// Never lies, which turns the oracle back into the normal game.
+pub struct Honest;
+
+impl LieStrategy for Honest {
+    fn lie(&mut self, _guess: Number, _truth: Outcome, _lies_left: usize) -> bool {
+        false
+    }
+}
+
+// Spends every lie as early as possible.
+pub struct Eager;
+
+impl LieStrategy for Eager {
+    fn lie(&mut self, _guess: Number, _truth: Outcome, lies_left: usize) -> bool {
+        lies_left > 0
+    }
+}
+
+// Lies with a fixed probability while it still has lies left.
+pub struct RandomLies<R: Rng> {
+    pub rng: R,
+    pub probability: f64,
+}
+
+impl<R: Rng> LieStrategy for RandomLies<R> {
+    fn lie(&mut self, _guess: Number, _truth: Outcome, lies_left: usize) -> bool {
+        lies_left > 0 && self.rng.gen_bool(self.probability)
+    }
+}
+
+// The strategies that `--liar` can pick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Liar {
+    Random,
+    Eager,
+    Honest,
+}
+
+impl std::str::FromStr for Liar {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Liar, String> {
+        match s {
+            "random" => Ok(Liar::Random),
+            "eager" => Ok(Liar::Eager),
+            "honest" => Ok(Liar::Honest),
+            other => Err(format!(
+                "unknown liar `{other}`, expected random, eager or honest"
+            )),
+        }
+    }
+}
+
+// Lets the strategy be picked at run time, for example from a command-line option.
+impl<S: LieStrategy + ?Sized> LieStrategy for Box<S> {
+    fn lie(&mut self, guess: Number, truth: Outcome, lies_left: usize) -> bool {
+        (**self).lie(guess, truth, lies_left)
+    }
+}
+
+pub struct LyingOracle<S: LieStrategy> {
+    secret: Number,
+    lies_left: usize,
+    strategy: S,
+}
+
+impl<S: LieStrategy> LyingOracle<S> {
+    pub fn new(secret: Number, max_lies: usize, strategy: S) -> LyingOracle<S> {
+        LyingOracle {
+            secret,
+            lies_left: max_lies,
+            strategy,
+        }
+    }
+
+    pub fn secret(&self) -> Number {
+        self.secret
+    }
+
+    // The same comparison as the normal game, except that the strategy may flip the answer.
+    pub fn answer(&mut self, guess: Number) -> Outcome {
+        let truth = Outcome::from(guess.cmp(&self.secret));
+        if truth == Outcome::Win || !self.strategy.lie(guess, truth, self.lies_left) {
+            return truth;
+        }
+
+        self.lies_left -= 1;
+        match truth {
+            Outcome::TooSmall => Outcome::TooLarge,
+            _ => Outcome::TooSmall,
+        }
+    }
+}
+
+// The number of ways `questions` answers can contain at most `lies` lies:
+// C(q, 0) + C(q, 1) + ... + C(q, lies). Saturates rather than overflowing.
+pub fn volume(questions: usize, lies: usize) -> u128 {
+    let mut total: u128 = 0;
+    let mut binomial: u128 = 1;
+    for i in 0..=lies.min(questions) {
+        total = total.saturating_add(binomial);
+        binomial = binomial.saturating_mul((questions - i) as u128) / (i as u128 + 1);
+    }
+    total
+}
+
+// The information-theoretic lower bound: every candidate number, together with every way
+// the lies could be placed, needs its own sequence of yes/no answers, so no strategy can be
+// sure of the number after fewer than the smallest `q` with `candidates * volume(q, lies) <= 2^q`.
+pub fn question_bound(candidates: u128, lies: usize) -> usize {
+    (0..)
+        .find(|&q: &usize| q >= 127 || candidates.saturating_mul(volume(q, lies)) <= 1u128 << q)
+        .unwrap()
+}
+
+// The most lies `--lies` accepts. Beyond a few dozen lies the volumes below saturate a
+// u128, and the solver does little better than trying the numbers in turn.
+pub const MAX_LIES: usize = 64;
+
+// Finds the secret despite up to `max_lies` lies using Berlekamp's volume method: every
+// candidate counts how many answers it contradicts, and each guess splits the remaining
+// volume as evenly as possible between "Too small" and "Too large".
+pub struct LiarSolver {
+    start: Number,
+    max_lies: usize,
+    // `contradictions[i]` belongs to the number `start + i`; anything above `max_lies` is ruled out.
+    contradictions: Vec<usize>,
+}
+
+impl LiarSolver {
+    // Keeps one counter per number, so the range has to fit comfortably in memory.
+    pub const MAX_CANDIDATES: usize = 1_000_000;
+
+    pub fn new(range: RangeInclusive<Number>, max_lies: usize) -> Result<LiarSolver, String> {
+        let size = (*range.end() as i128 - *range.start() as i128 + 1).max(0);
+        if size == 0 || size > LiarSolver::MAX_CANDIDATES as i128 {
+            return Err(format!(
+                "the lying game supports between 1 and {} numbers, got {size}",
+                LiarSolver::MAX_CANDIDATES
+            ));
+        }
+
+        Ok(LiarSolver {
+            start: *range.start(),
+            max_lies,
+            contradictions: vec![0; size as usize],
+        })
+    }
+
+    fn number(&self, index: usize) -> Number {
+        self.start + index as Number
+    }
+
+    // How many more questions the bound says are needed from here.
+    pub fn remaining_bound(&self) -> usize {
+        (0..)
+            .find(|&q| q >= 127 || self.total_volume(q) <= 1u128 << q)
+            .unwrap()
+    }
+
+    fn total_volume(&self, questions: usize) -> u128 {
+        self.contradictions
+            .iter()
+            .filter(|&&count| count <= self.max_lies)
+            .fold(0u128, |total, &count| {
+                total.saturating_add(volume(questions, self.max_lies - count))
+            })
+    }
+
+    pub fn next_guess(&self) -> Number {
+        let questions = self.remaining_bound().saturating_sub(1);
+        // What a candidate is worth after the next answer, depending on whether
+        // that answer agrees with it (`keep`) or costs it another lie (`worse`).
+        let weight = |count: usize, extra: usize| {
+            if count.saturating_add(extra) > self.max_lies {
+                0
+            } else {
+                volume(questions, self.max_lies - count - extra)
+            }
+        };
+
+        let live: Vec<usize> = (0..self.contradictions.len())
+            .filter(|&index| self.contradictions[index] <= self.max_lies)
+            .collect();
+        if live.len() == 1 {
+            return self.number(live[0]);
+        }
+
+        // Guessing `live[i]` and hearing "Too small" keeps every candidate above it and charges
+        // every candidate below it a lie; "Too large" is the mirror image.
+        let keep: Vec<u128> = live
+            .iter()
+            .map(|&i| weight(self.contradictions[i], 0))
+            .collect();
+        let worse: Vec<u128> = live
+            .iter()
+            .map(|&i| weight(self.contradictions[i], 1))
+            .collect();
+        // The volumes saturate once there are enough lies, so the sums above each position
+        // come from a scan from the top rather than from subtracting out of a total.
+        let mut above = vec![(0u128, 0u128); live.len()];
+        for position in (1..live.len()).rev() {
+            let (keep_above, worse_above) = above[position];
+            above[position - 1] = (
+                keep_above.saturating_add(keep[position]),
+                worse_above.saturating_add(worse[position]),
+            );
+        }
+
+        let mut best = (u128::MAX, live[0]);
+        let (mut keep_below, mut worse_below) = (0u128, 0u128);
+        for (position, &index) in live.iter().enumerate() {
+            let (keep_above, worse_above) = above[position];
+            let too_small = keep_above.saturating_add(worse_below);
+            let too_large = keep_below.saturating_add(worse_above);
+            let worst = too_small.max(too_large);
+            if worst < best.0 {
+                best = (worst, index);
+            }
+            keep_below = keep_below.saturating_add(keep[position]);
+            worse_below = worse_below.saturating_add(worse[position]);
+        }
+
+        self.number(best.1)
+    }
+
+    pub fn record(&mut self, guess: Number, answer: Outcome) {
+        for index in 0..self.contradictions.len() {
+            let candidate = self.number(index);
+            // A correct guess is never a lie, so `Win` settles the game outright.
+            let agrees = match answer {
+                Outcome::Win => candidate == guess,
+                _ => candidate != guess && Outcome::from(guess.cmp(&candidate)) == answer,
+            };
+            if !agrees {
+                let cost = if candidate == guess || answer == Outcome::Win {
+                    self.max_lies.saturating_add(1)
+                } else {
+                    1
+                };
+                self.contradictions[index] = self.contradictions[index].saturating_add(cost);
+            }
+        }
+    }
+}
+
+// Plays the solver against an oracle until it wins, returning every guess and answer.
+pub fn solve<S: LieStrategy>(
+    solver: &mut LiarSolver,
+    oracle: &mut LyingOracle<S>,
+) -> Vec<(Number, Outcome)> {
+    let mut history = Vec::new();
+    loop {
+        let guess = solver.next_guess();
+        let answer = oracle.answer(guess);
+        history.push((guess, answer));
+        if answer == Outcome::Win {
+            return history;
+        }
+        solver.record(guess, answer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn volume_and_bound() {
+        assert_eq!(volume(10, 0), 1);
+        assert_eq!(volume(10, 1), 11);
+        assert_eq!(volume(10, 2), 56);
+        // The classic result: one lie and a million numbers needs 25 questions.
+        assert_eq!(question_bound(1_000_000, 1), 25);
+        assert_eq!(question_bound(100, 0), 7);
+    }
+
+    #[test]
+    fn oracle_lies_at_most_k_times() {
+        let mut oracle = LyingOracle::new(50, 2, Eager);
+        assert_eq!(oracle.answer(10), Outcome::TooLarge);
+        assert_eq!(oracle.answer(10), Outcome::TooLarge);
+        assert_eq!(oracle.answer(10), Outcome::TooSmall);
+        assert_eq!(oracle.answer(50), Outcome::Win);
+    }
+
+    #[test]
+    fn solver_finds_every_number_within_the_bound() {
+        for lies in 0..=2 {
+            let bound = question_bound(100, lies);
+            for secret in 1..=100 {
+                let mut solver = LiarSolver::new(1..=100, lies).unwrap();
+                let mut oracle = LyingOracle::new(secret, lies, Eager);
+                let history = solve(&mut solver, &mut oracle);
+                assert!(history.len() <= bound + 1, "{lies} lies, secret {secret}");
+
+                let mut solver = LiarSolver::new(1..=100, lies).unwrap();
+                let strategy = RandomLies {
+                    rng: StdRng::seed_from_u64(secret as u64),
+                    probability: 0.5,
+                };
+                let mut oracle = LyingOracle::new(secret, lies, strategy);
+                let history = solve(&mut solver, &mut oracle);
+                assert!(history.len() <= bound + 1, "{lies} lies, secret {secret}");
+            }
+        }
+    }
+
+    #[test]
+    fn solver_survives_saturated_volumes() {
+        let lies = MAX_LIES;
+        for secret in [1, 50, 100] {
+            let mut solver = LiarSolver::new(1..=100, lies).unwrap();
+            let mut oracle = LyingOracle::new(secret, lies, Eager);
+            let history = solve(&mut solver, &mut oracle);
+            assert_eq!(history.last(), Some(&(secret, Outcome::Win)));
+        }
+    }
+
+    #[test]
+    fn too_many_candidates() {
+        assert!(LiarSolver::new(Number::MIN..=Number::MAX, 1).is_err());
+        let (low, high) = (1, 0);
+        assert!(LiarSolver::new(low..=high, 1).is_err());
+    }
+}
@@ -1,35 +1,688 @@
+mod bulls;
+mod config;
+mod game;
+mod hints;
+mod leaderboard;
+mod liar;
+mod messages;
+mod replay;
+mod reverse;
+mod save;
+mod server;
+mod settings;
+mod strategy;
+mod timer;
+
+use bulls::BullsAndCows;
+use config::Config;
+use game::{Ending, Frontend, GuessingGame, Number, Outcome};
+use hints::{Band, Hint, Trend};
+use leaderboard::{Leaderboard, Record, Report};
+use liar::{Eager, Honest, Liar, LiarSolver, LieStrategy, LyingOracle, RandomLies};
+use messages::{message, text, Lang};
 use rand::Rng;
-use std::{cmp::Ordering, io};
+use replay::Session;
+use reverse::ReverseGame;
+use std::net::TcpListener;
+use std::ops::RangeInclusive;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use std::{env, io, process, thread};
+use strategy::{BinarySearch, Hedged, Linear, RandomGuess, Strategy};
+use timer::{Reply, SystemClock, TimeLimits, TimedFrontend};
 
-fn main() {
-    println!("Guess the number!");
+// Exit statuses, so scripts can tell how a game ended: 0 is a win, 1 an I/O problem
+// and 2 bad arguments.
+const EXIT_ABANDONED: i32 = 3;
+const EXIT_LOST: i32 = 4;
 
-    let secret_number = rand::thread_rng().gen_range(1..=100);
-    // println!("The secret number is: {secret_number}");
+const DEFAULT_LEADERBOARD: &str = "leaderboard.txt";
+const DEFAULT_IDLE_SECS: u64 = 60;
 
-    loop {
-        println!("Please input your guess.");
+// The original guessing game loop, now as a front end that reads from stdin and prints to stdout.
+// In batch mode the prompts are left out, which keeps the output of scripted runs short.
+struct Terminal {
+    batch: bool,
+    lang: Lang,
+    // Only used by timed games, which cannot block on stdin: a thread reads the lines instead.
+    lines: Option<Receiver<String>>,
+}
+
+impl Terminal {
+    // Prompts for and reads one line, or returns `None` at end of input.
+    fn read_input(&self) -> Option<String> {
+        if !self.batch {
+            println!("{}", text(self.lang, "prompt"));
+        }
 
         let mut guess = String::new();
 
-        io::stdin()
+        // `read_line` returns `Ok(0)` at end of input. Without this check the empty
+        // string fails to parse and we would ask for another guess forever.
+        let bytes = io::stdin()
             .read_line(&mut guess)
             .expect("Failed to read line.");
+        if bytes == 0 {
+            None
+        } else {
+            Some(guess)
+        }
+    }
+
+    fn lines(&mut self) -> &Receiver<String> {
+        self.lines.get_or_insert_with(|| {
+            let (tx, rx) = mpsc::channel();
+            thread::spawn(move || {
+                for line in io::stdin().lines() {
+                    let Ok(line) = line else { break };
+                    if tx.send(line).is_err() {
+                        break;
+                    }
+                }
+            });
+            rx
+        })
+    }
+}
+
+impl Frontend for Terminal {
+    fn start(&mut self, game: &GuessingGame) {
+        println!("{}", text(self.lang, "guess-the-number"));
+        println!(
+            "{}",
+            message(
+                self.lang,
+                "range",
+                &[("low", game.range().start()), ("high", game.range().end())]
+            )
+        );
+        if let Some(max) = game.max_attempts() {
+            println!("{}", message(self.lang, "attempts", &[("max", &max)]));
+        }
+        // A resumed game starts by repeating what happened before it was saved.
+        for &(guess, outcome) in game.history() {
+            self.report(guess, outcome);
+        }
+    }
+
+    // Typing `save` (or `save <file>`) instead of a number saves the game and keeps playing.
+    fn read_guess(&mut self, game: &GuessingGame) -> Option<Number> {
+        loop {
+            let guess = self.read_input()?;
+            let guess = guess.trim();
 
-        let guess: u32 = match guess.trim().parse() {
-            Ok(num) => num,
-            Err(_) => continue,
+            if let Some(path) = save::save_command(guess) {
+                match save::save(game, path) {
+                    Ok(()) => println!("{}", message(self.lang, "saved", &[("path", &path)])),
+                    Err(e) => eprintln!(
+                        "{}",
+                        message(self.lang, "save-failed", &[("path", &path), ("error", &e)])
+                    ),
+                }
+                continue;
+            }
+
+            match guess.parse() {
+                Ok(num) => return Some(num),
+                Err(_) => eprintln!(
+                    "{}",
+                    message(self.lang, "invalid-guess", &[("guess", &guess)])
+                ),
+            };
+        }
+    }
+
+    fn report(&mut self, guess: Number, outcome: Outcome) {
+        println!(
+            "{}",
+            message(self.lang, "you-guessed", &[("guess", &guess)])
+        );
+
+        let id = match outcome {
+            Outcome::TooSmall => "too-small",
+            Outcome::TooLarge => "too-large",
+            Outcome::Win => "you-win",
         };
+        println!("{}", text(self.lang, id));
+    }
+
+    fn hint(&mut self, hint: Hint) {
+        let band = text(
+            self.lang,
+            match hint.band {
+                Band::Freezing => "hint-freezing",
+                Band::Cold => "hint-cold",
+                Band::Warm => "hint-warm",
+                Band::Hot => "hint-hot",
+            },
+        );
+        let id = match hint.trend {
+            None => {
+                println!("{band}");
+                return;
+            }
+            Some(Trend::Warmer) => "hint-warmer",
+            Some(Trend::Colder) => "hint-colder",
+            Some(Trend::Same) => "hint-same",
+        };
+        println!("{}", message(self.lang, id, &[("band", &band)]));
+    }
+}
+
+// Saving is left out of timed games, since it would be an easy way to stop the clock.
+impl TimedFrontend for Terminal {
+    fn read_guess_within(&mut self, _game: &GuessingGame, limit: Option<Duration>) -> Reply {
+        let deadline = limit.map(|limit| Instant::now() + limit);
+        loop {
+            if !self.batch {
+                println!("{}", text(self.lang, "prompt"));
+            }
+            let line = match deadline {
+                Some(deadline) => self
+                    .lines()
+                    .recv_timeout(deadline.saturating_duration_since(Instant::now())),
+                None => self
+                    .lines()
+                    .recv()
+                    .map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            let guess = match line {
+                Ok(line) => line,
+                Err(RecvTimeoutError::Timeout) => return Reply::TimedOut,
+                Err(RecvTimeoutError::Disconnected) => return Reply::Closed,
+            };
+            match guess.trim().parse() {
+                Ok(num) => return Reply::Guess(num),
+                Err(_) => eprintln!(
+                    "{}",
+                    message(self.lang, "invalid-guess", &[("guess", &guess.trim())])
+                ),
+            }
+        }
+    }
+
+    fn timed_out(&mut self) {
+        println!("{}", text(self.lang, "timed-out"));
+    }
+}
+
+fn main() {
+    let config = Config::build(env::args()).unwrap_or_else(|err| {
+        let lang = Lang::from_env();
+        eprintln!("{}", message(lang, "bad-arguments", &[("error", &err)]));
+        process::exit(2);
+    });
+
+    let lang = config.lang.unwrap_or_else(Lang::from_env);
+    let mut terminal = Terminal {
+        batch: config.batch,
+        lang,
+        lines: None,
+    };
 
-        println!("You guessed {}", guess);
+    if config.untranslated {
+        let missing = messages::untranslated(lang);
+        for id in &missing {
+            println!("{id}");
+        }
+        if !missing.is_empty() {
+            eprintln!(
+                "{}",
+                message(
+                    lang,
+                    "untranslated-count",
+                    &[("count", &missing.len()), ("lang", &lang)]
+                )
+            );
+            process::exit(1);
+        }
+        return;
+    }
+
+    if let Some(path) = &config.replay {
+        play_back(path, terminal);
+        return;
+    }
+
+    let leaderboard_path = config.leaderboard.as_deref().unwrap_or(DEFAULT_LEADERBOARD);
+
+    if let Some(report) = config.show {
+        show(leaderboard_path, report, lang);
+        return;
+    }
 
-        match guess.cmp(&secret_number) {
-            Ordering::Less => println!("Too small"),
-            Ordering::Greater => println!("Too large"),
-            Ordering::Equal => {
-                println!("You win");
-                break;
+    let settings = config.settings().unwrap_or_else(|err| {
+        eprintln!("{}", message(lang, "bad-settings", &[("error", &err)]));
+        process::exit(2);
+    });
+
+    if config.reverse {
+        play_reverse(ReverseGame::new(settings.range), lang);
+        return;
+    }
+
+    // Without `--seed` we still pick one, so that every session can be recorded and replayed.
+    let seed = config.seed.unwrap_or_else(|| rand::thread_rng().gen());
+    let mut rng = replay::seeded_rng(seed);
+
+    if let Some(games) = config.tournament {
+        let mut strategies: Vec<Box<dyn Strategy>> = vec![
+            Box::new(BinarySearch::default()),
+            Box::new(RandomGuess::new(replay::seeded_rng(rng.gen()))),
+            Box::new(Linear::default()),
+            Box::new(Hedged::new(replay::seeded_rng(rng.gen()))),
+        ];
+        let stats = strategy::tournament(&mut strategies, &settings, games, &mut rng);
+        if config.csv {
+            print!("{}", strategy::csv(&stats));
+        } else {
+            print!("{}", strategy::table(&stats));
+        }
+        return;
+    }
+
+    if let Some(addr) = config.serve {
+        let game = GuessingGame::with_rng(&mut rng, settings.range);
+        let idle = Duration::from_secs(config.idle.unwrap_or(DEFAULT_IDLE_SECS));
+        let winner = TcpListener::bind(addr).and_then(|listener| {
+            println!("{}", message(lang, "server-waiting", &[("addr", &addr)]));
+            server::serve(listener, game, idle, lang)
+        });
+        match winner {
+            Ok(winner) => println!(
+                "{}",
+                message(
+                    lang,
+                    "server-winner",
+                    &[("name", &winner.name), ("guesses", &winner.guesses)]
+                )
+            ),
+            Err(e) => {
+                eprintln!(
+                    "{}",
+                    message(lang, "server-failed", &[("addr", &addr), ("error", &e)])
+                );
+                process::exit(1);
             }
         }
+        return;
+    }
+
+    if let Some(length) = config.bulls {
+        let game = BullsAndCows::with_rng(&mut rng, length).unwrap_or_else(|err| {
+            eprintln!("{}", message(lang, "bad-settings", &[("error", &err)]));
+            process::exit(2);
+        });
+        play_bulls(game.with_max_attempts(settings.max_attempts), terminal);
+        return;
+    }
+
+    if let Some(lies) = config.lies {
+        let secret = rng.gen_range(settings.range.clone());
+        let strategy: Box<dyn LieStrategy> = match config.liar.unwrap_or(Liar::Random) {
+            Liar::Random => Box::new(RandomLies {
+                rng,
+                probability: 0.5,
+            }),
+            Liar::Eager => Box::new(Eager),
+            Liar::Honest => Box::new(Honest),
+        };
+        let oracle = LyingOracle::new(secret, lies, strategy);
+        play_lying(oracle, settings.range, lies, config.solve, terminal);
+        return;
+    }
+
+    let game = match &config.resume {
+        Some(path) => save::load(path).unwrap_or_else(|e| {
+            eprintln!(
+                "{}",
+                message(lang, "resume-failed", &[("path", path), ("error", &e)])
+            );
+            process::exit(1);
+        }),
+        None => GuessingGame::with_rng(&mut rng, settings.range)
+            .with_max_attempts(settings.max_attempts),
+    };
+    let mut game = game.with_hints(config.hints);
+
+    let limits = config.time_limits();
+    let (ending, duration) = if limits == TimeLimits::default() {
+        let started = Instant::now();
+        let ending = game::play(&mut game, &mut terminal);
+        (ending, started.elapsed())
+    } else {
+        if let Some(limit) = limits.per_guess {
+            let seconds = limit.as_secs();
+            println!("{}", message(lang, "time-limit", &[("seconds", &seconds)]));
+        }
+        if let Some(total) = limits.total {
+            let seconds = total.as_secs();
+            println!("{}", message(lang, "total-time", &[("seconds", &seconds)]));
+        }
+        let (ending, duration) =
+            timer::play_timed(&mut game, &mut terminal, &SystemClock::new(), limits);
+        let attempts = game.attempts_used();
+        let score = timer::score(&ending, attempts, duration);
+        let seconds = format!("{:.1}", duration.as_secs_f64());
+        println!(
+            "{}",
+            message(
+                lang,
+                "score",
+                &[
+                    ("score", &score),
+                    ("attempts", &attempts),
+                    ("seconds", &seconds)
+                ]
+            )
+        );
+        (ending, duration)
+    };
+
+    if let Some(path) = &config.record {
+        if let Err(e) = Session::from_game(seed, &game).save(path) {
+            eprintln!(
+                "{}",
+                message(lang, "record-failed", &[("path", path), ("error", &e)])
+            );
+            process::exit(1);
+        }
+    }
+
+    // Only finished games count; an abandoned one says nothing about the player. Neither
+    // does a resumed one, whose rules and time partly come from an earlier run.
+    if config.resume.is_none() && !matches!(ending, Ending::Abandoned { .. }) {
+        let record = Record {
+            player: config
+                .player
+                .clone()
+                .unwrap_or_else(|| String::from("anonymous")),
+            difficulty: config.difficulty_label(),
+            low: *game.range().start(),
+            high: *game.range().end(),
+            guesses: game.history().len(),
+            won: game.is_won(),
+            duration,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or(0),
+        };
+        // Losing the score is not worth failing the game over, so this only warns.
+        if let Err(e) = Leaderboard::append(leaderboard_path, &record) {
+            eprintln!(
+                "{}",
+                message(
+                    lang,
+                    "leaderboard-write-failed",
+                    &[("path", &leaderboard_path), ("error", &e)]
+                )
+            );
+        }
+    }
+
+    match ending {
+        Ending::Won { .. } => {}
+        Ending::Lost { secret } => {
+            println!("{}", message(lang, "you-lose", &[("secret", &secret)]));
+            process::exit(EXIT_LOST);
+        }
+        Ending::Abandoned { guesses } => {
+            println!("{}", message(lang, "abandoned", &[("guesses", &guesses)]));
+            process::exit(EXIT_ABANDONED);
+        }
+        Ending::OutOfTime { secret } => {
+            println!("{}", message(lang, "out-of-time", &[("secret", &secret)]));
+            process::exit(EXIT_LOST);
+        }
+    }
+}
+
+fn play_back(path: &str, mut terminal: Terminal) {
+    let session = Session::load(path).unwrap_or_else(|e| {
+        eprintln!(
+            "{}",
+            message(
+                terminal.lang,
+                "replay-read-failed",
+                &[("path", &path), ("error", &e)]
+            )
+        );
+        process::exit(1);
+    });
+
+    println!(
+        "{}",
+        message(terminal.lang, "replaying", &[("seed", &session.seed)])
+    );
+    match session.replay() {
+        Ok(game) => {
+            for &(guess, outcome) in game.history() {
+                terminal.report(guess, outcome);
+            }
+        }
+        Err(e) => {
+            eprintln!(
+                "{}",
+                message(terminal.lang, "replay-diverged", &[("error", &e)])
+            );
+            process::exit(1);
+        }
+    }
+}
+
+fn show(path: &str, report: Report, lang: Lang) {
+    let leaderboard = Leaderboard::load(path).unwrap_or_else(|e| {
+        eprintln!(
+            "{}",
+            message(
+                lang,
+                "leaderboard-read-failed",
+                &[("path", &path), ("error", &e)]
+            )
+        );
+        Leaderboard::default()
+    });
+    if leaderboard.skipped > 0 {
+        eprintln!(
+            "{}",
+            message(
+                lang,
+                "leaderboard-skipped",
+                &[("count", &leaderboard.skipped), ("path", &path)]
+            )
+        );
+    }
+
+    match report {
+        Report::Top => {
+            for (difficulty, records) in leaderboard.top_scores(5) {
+                println!("{difficulty}:");
+                for (rank, record) in records.iter().enumerate() {
+                    let seconds = format!("{:.1}", record.duration.as_secs_f64());
+                    println!(
+                        "{}",
+                        message(
+                            lang,
+                            "top-score",
+                            &[
+                                ("rank", &(rank + 1)),
+                                ("player", &record.player),
+                                ("guesses", &record.guesses),
+                                ("seconds", &seconds)
+                            ]
+                        )
+                    );
+                }
+            }
+        }
+        Report::Players => {
+            for (player, average) in leaderboard.average_guesses() {
+                let average = format!("{average:.2}");
+                println!(
+                    "{}",
+                    message(
+                        lang,
+                        "average-guesses",
+                        &[("player", &player), ("average", &average)]
+                    )
+                );
+            }
+        }
+        Report::Histogram => {
+            for (guesses, games) in leaderboard.histogram() {
+                println!("{guesses:>3} | {} {games}", "#".repeat(games));
+            }
+        }
+    }
+}
+
+// The player thinks of a number and answers our guesses.
+fn play_reverse(mut game: ReverseGame, lang: Lang) {
+    println!("{}", text(lang, "reverse-intro"));
+    println!("{}", text(lang, "reverse-how"));
+
+    loop {
+        let guess = game.next_guess();
+        println!("{}", message(lang, "reverse-ask", &[("guess", &guess)]));
+
+        let reply = loop {
+            let mut reply = String::new();
+            let bytes = io::stdin()
+                .read_line(&mut reply)
+                .expect("Failed to read line.");
+            if bytes == 0 {
+                let guesses = game.replies().len();
+                println!("{}", message(lang, "abandoned", &[("guesses", &guesses)]));
+                process::exit(EXIT_ABANDONED);
+            }
+            match reverse::parse_reply(&reply) {
+                Some(outcome) => break outcome,
+                None => eprintln!(
+                    "{}",
+                    message(lang, "reverse-bad-reply", &[("reply", &reply.trim())])
+                ),
+            }
+        };
+
+        if let Err(contradiction) = game.reply(guess, reply) {
+            println!("{}", contradiction.message(lang));
+            process::exit(1);
+        }
+        if reply == Outcome::Win {
+            let guesses = game.replies().len();
+            println!(
+                "{}",
+                message(lang, "reverse-got-it", &[("guesses", &guesses)])
+            );
+            return;
+        }
+    }
+}
+
+// Ulam's game: the answers may include up to `lies` lies. With `--solve` the program
+// plays against itself and shows how close it gets to the theoretical bound.
+fn play_lying(
+    mut oracle: LyingOracle<Box<dyn LieStrategy>>,
+    range: RangeInclusive<Number>,
+    lies: usize,
+    solve: bool,
+    mut terminal: Terminal,
+) {
+    if solve {
+        let size = (*range.end() as i128 - *range.start() as i128 + 1) as u128;
+        let mut solver = LiarSolver::new(range, lies).unwrap_or_else(|err| {
+            eprintln!(
+                "{}",
+                message(terminal.lang, "bad-settings", &[("error", &err)])
+            );
+            process::exit(2);
+        });
+        let bound = liar::question_bound(size, lies);
+        let history = liar::solve(&mut solver, &mut oracle);
+        for &(guess, outcome) in &history {
+            terminal.report(guess, outcome);
+        }
+        println!(
+            "{}",
+            message(
+                terminal.lang,
+                "lies-solved",
+                &[
+                    ("secret", &oracle.secret()),
+                    ("guesses", &history.len()),
+                    ("bound", &bound)
+                ]
+            )
+        );
+        return;
+    }
+
+    println!(
+        "{}",
+        message(terminal.lang, "lies-intro", &[("lies", &lies)])
+    );
+    let mut guesses = 0;
+    loop {
+        let Some(input) = terminal.read_input() else {
+            println!(
+                "{}",
+                message(terminal.lang, "abandoned", &[("guesses", &guesses)])
+            );
+            process::exit(EXIT_ABANDONED);
+        };
+        let Ok(guess) = input.trim().parse() else {
+            eprintln!(
+                "{}",
+                message(terminal.lang, "invalid-guess", &[("guess", &input.trim())])
+            );
+            continue;
+        };
+        guesses += 1;
+        let outcome = oracle.answer(guess);
+        terminal.report(guess, outcome);
+        if outcome == Outcome::Win {
+            return;
+        }
+    }
+}
+
+// Bulls and cows: the secret is a code of distinct digits rather than a number.
+// It reads guesses and counts attempts the same way as the normal game.
+fn play_bulls(mut game: BullsAndCows, terminal: Terminal) {
+    let lang = terminal.lang;
+    println!(
+        "{}",
+        message(lang, "bulls-intro", &[("length", &game.len())])
+    );
+    if let Some(max) = game.attempts().max() {
+        println!("{}", message(lang, "attempts", &[("max", &max)]));
+    }
+
+    while !game.is_won() {
+        if game.is_lost() {
+            println!(
+                "{}",
+                message(lang, "bulls-lose", &[("secret", &game.secret())])
+            );
+            process::exit(EXIT_LOST);
+        }
+        let Some(input) = terminal.read_input() else {
+            let guesses = game.attempts().used();
+            println!("{}", message(lang, "abandoned", &[("guesses", &guesses)]));
+            process::exit(EXIT_ABANDONED);
+        };
+        match game.submit(&input) {
+            Ok(score) => println!(
+                "{}",
+                message(
+                    lang,
+                    "bulls-score",
+                    &[("bulls", &score.bulls), ("cows", &score.cows)]
+                )
+            ),
+            Err(err) => eprintln!("{}", err.message(lang)),
+        }
     }
+    println!("{}", text(lang, "you-win"));
 }
@@ -0,0 +1,420 @@
+use std::env;
+use std::fmt;
+use std::str::FromStr;
+
+// Every message the player sees, looked up by id so it can be shown in more than one language.
+// Placeholders such as `{guess}` are filled in by `message`.
+
+// The environment variable that picks a language when `--lang` is not given.
+pub const LANGUAGE_VAR: &str = "BOOK_LANG";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Lang {
+    #[default]
+    En,
+    ZhCn,
+}
+
+impl FromStr for Lang {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Lang, String> {
+        match s {
+            "en" => Ok(Lang::En),
+            "zh-CN" | "zh" => Ok(Lang::ZhCn),
+            other => Err(format!("unknown language `{other}`, expected en or zh-CN")),
+        }
+    }
+}
+
+impl fmt::Display for Lang {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Lang::En => write!(f, "en"),
+            Lang::ZhCn => write!(f, "zh-CN"),
+        }
+    }
+}
+
+impl Lang {
+    // An unset or unknown language quietly means English, since a bad environment
+    // variable is not worth refusing to start over.
+    pub fn from_env() -> Lang {
+        env::var(LANGUAGE_VAR)
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or_default()
+    }
+}
+
+const EN: &[(&str, &str)] = &[
+    ("guess-the-number", "Guess the number!"),
+    (
+        "range",
+        "The secret number will be between {low} and {high}.",
+    ),
+    ("attempts", "You have {max} attempts."),
+    ("prompt", "Please input your guess."),
+    ("invalid-guess", "`{guess}` is not a valid guess."),
+    ("you-guessed", "You guessed {guess}"),
+    ("too-small", "Too small"),
+    ("too-large", "Too large"),
+    ("you-win", "You win"),
+    ("you-lose", "You lose, the secret number was {secret}."),
+    ("abandoned", "Game abandoned after {guesses} guesses."),
+    (
+        "saved",
+        "Game saved to {path}. Resume it with --resume {path}",
+    ),
+    ("time-limit", "You have {seconds} seconds for each guess."),
+    ("total-time", "You have {seconds} seconds in total."),
+    ("timed-out", "Too slow! That attempt is lost."),
+    ("out-of-time", "Time is up, the secret number was {secret}."),
+    (
+        "score",
+        "Score: {score} ({attempts} attempts in {seconds} seconds)",
+    ),
+    ("hint-freezing", "Freezing"),
+    ("hint-cold", "Cold"),
+    ("hint-warm", "Warm"),
+    ("hint-hot", "Hot"),
+    ("hint-warmer", "{band}, warmer than last time"),
+    ("hint-colder", "{band}, colder than last time"),
+    ("hint-same", "{band}, no closer than last time"),
+    (
+        "bulls-intro",
+        "Guess the {length}-digit code! Every digit is different.",
+    ),
+    ("bulls-score", "{bulls} bulls, {cows} cows"),
+    ("bulls-lose", "You lose, the secret code was {secret}."),
+    (
+        "lies-intro",
+        "Guess the number! Up to {lies} of my answers may be lies.",
+    ),
+    ("reverse-intro", "Think of a number and I will guess it."),
+    (
+        "reverse-how",
+        "Answer each guess with higher, lower or correct.",
+    ),
+    ("reverse-ask", "Is it {guess}?"),
+    ("reverse-got-it", "I got it in {guesses} guesses."),
+    (
+        "reverse-bad-reply",
+        "`{reply}` is not higher, lower or correct.",
+    ),
+    ("number-higher", "higher than {guess}"),
+    ("number-lower", "lower than {guess}"),
+    ("number-equal", "equal to {guess}"),
+    (
+        "contradiction-reply",
+        "You said the number is {now}, but reply #{index} said it is {then}.",
+    ),
+    (
+        "contradiction-range",
+        "You said the number is {now}, but it has to be between {low} and {high}.",
+    ),
+    ("code-not-digits", "`{code}` should only contain digits"),
+    ("code-wrong-length", "`{code}` should have {length} digits"),
+    ("code-repeats", "`{code}` repeats the digit {digit}"),
+    (
+        "lies-solved",
+        "Found {secret} after {guesses} guesses; in the worst case no strategy can do it in fewer than {bound} questions.",
+    ),
+    ("replaying", "Replaying seed {seed}"),
+    ("replay-diverged", "Replay diverged: {error}"),
+    (
+        "top-score",
+        "  {rank}. {player} - {guesses} guesses in {seconds}s",
+    ),
+    ("average-guesses", "{player}: {average} guesses per game"),
+    (
+        "leaderboard-skipped",
+        "Skipped {count} unreadable lines in {path}.",
+    ),
+    (
+        "untranslated-count",
+        "{count} messages have no {lang} translation.",
+    ),
+    ("server-waiting", "Waiting for players on {addr}"),
+    (
+        "server-winner",
+        "{name} won after {guesses} guesses in total.",
+    ),
+    ("server-not-a-number", "`{number}` is not a number"),
+    ("server-unknown-command", "unknown command `{line}`"),
+    ("server-name-taken", "the name `{name}` is taken"),
+    (
+        "server-out-of-range",
+        "`{guess}` is not between {low} and {high}",
+    ),
+    ("bad-arguments", "Problem parsing arguments: {error}"),
+    ("bad-settings", "Problem with game settings: {error}"),
+    (
+        "save-failed",
+        "Problem saving the game to {path}: {error}",
+    ),
+    (
+        "resume-failed",
+        "Problem resuming the game from {path}: {error}",
+    ),
+    (
+        "record-failed",
+        "Problem writing replay file {path}: {error}",
+    ),
+    (
+        "replay-read-failed",
+        "Problem reading replay file {path}: {error}",
+    ),
+    (
+        "leaderboard-read-failed",
+        "Problem reading leaderboard {path}: {error}",
+    ),
+    (
+        "leaderboard-write-failed",
+        "Problem writing leaderboard {path}: {error}",
+    ),
+    (
+        "server-failed",
+        "Problem running the server on {addr}: {error}",
+    ),
+];
+
+const ZH_CN: &[(&str, &str)] = &[
+    ("guess-the-number", "猜数字！"),
+    ("range", "秘密数字在 {low} 和 {high} 之间。"),
+    ("attempts", "你有 {max} 次机会。"),
+    ("prompt", "请输入你的猜测。"),
+    ("invalid-guess", "`{guess}` 不是有效的猜测。"),
+    ("you-guessed", "你猜的是 {guess}"),
+    ("too-small", "太小了"),
+    ("too-large", "太大了"),
+    ("you-win", "你赢了"),
+    ("you-lose", "你输了，秘密数字是 {secret}。"),
+    ("abandoned", "猜了 {guesses} 次后放弃了游戏。"),
+    ("saved", "游戏已保存到 {path}。用 --resume {path} 继续。"),
+    ("time-limit", "每次猜测有 {seconds} 秒。"),
+    ("total-time", "总共有 {seconds} 秒。"),
+    ("timed-out", "太慢了！这次机会作废。"),
+    ("out-of-time", "时间到了，秘密数字是 {secret}。"),
+    (
+        "score",
+        "得分：{score}（{attempts} 次机会，用时 {seconds} 秒）",
+    ),
+    ("hint-freezing", "冰冷"),
+    ("hint-cold", "冷"),
+    ("hint-warm", "温暖"),
+    ("hint-hot", "热"),
+    ("hint-warmer", "{band}，比上次更近"),
+    ("hint-colder", "{band}，比上次更远"),
+    ("hint-same", "{band}，没有比上次更近"),
+    (
+        "bulls-intro",
+        "猜一个 {length} 位的密码！每一位数字都不同。",
+    ),
+    (
+        "bulls-score",
+        "{bulls} 个位置正确，{cows} 个数字正确但位置不对",
+    ),
+    ("bulls-lose", "你输了，密码是 {secret}。"),
+    ("lies-intro", "猜数字！我的回答里最多有 {lies} 次谎话。"),
+    ("reverse-intro", "想一个数字，我来猜。"),
+    (
+        "reverse-how",
+        "请用 higher、lower 或 correct 回答每次猜测。",
+    ),
+    ("reverse-ask", "是 {guess} 吗？"),
+    ("reverse-got-it", "我猜了 {guesses} 次就猜中了。"),
+    (
+        "reverse-bad-reply",
+        "`{reply}` 不是 higher、lower 或 correct。",
+    ),
+    ("number-higher", "大于 {guess}"),
+    ("number-lower", "小于 {guess}"),
+    ("number-equal", "等于 {guess}"),
+    (
+        "contradiction-reply",
+        "你说这个数字{now}，但第 {index} 次回答说它{then}。",
+    ),
+    (
+        "contradiction-range",
+        "你说这个数字{now}，但它必须在 {low} 和 {high} 之间。",
+    ),
+    ("code-not-digits", "`{code}` 只能包含数字"),
+    ("code-wrong-length", "`{code}` 应该有 {length} 位数字"),
+    ("code-repeats", "`{code}` 重复了数字 {digit}"),
+    (
+        "lies-solved",
+        "猜了 {guesses} 次找到了 {secret}；在最坏的情况下，任何策略都至少需要 {bound} 个问题。",
+    ),
+    ("replaying", "正在回放种子 {seed}"),
+    ("replay-diverged", "回放与记录不一致：{error}"),
+    (
+        "top-score",
+        "  {rank}. {player} - 用时 {seconds} 秒，猜了 {guesses} 次",
+    ),
+    ("average-guesses", "{player}：平均每局猜 {average} 次"),
+    (
+        "leaderboard-skipped",
+        "跳过了 {path} 中 {count} 行无法读取的内容。",
+    ),
+    ("untranslated-count", "有 {count} 条消息没有 {lang} 翻译。"),
+    ("server-waiting", "正在 {addr} 等待玩家"),
+    ("server-winner", "{name} 赢了，大家一共猜了 {guesses} 次。"),
+    ("server-not-a-number", "`{number}` 不是数字"),
+    ("server-unknown-command", "未知命令 `{line}`"),
+    ("server-name-taken", "名字 `{name}` 已经有人用了"),
+    ("server-out-of-range", "`{guess}` 不在 {low} 和 {high} 之间"),
+    ("bad-arguments", "参数有误：{error}"),
+    ("bad-settings", "游戏设置有误：{error}"),
+    ("save-failed", "无法将游戏保存到 {path}：{error}"),
+    ("resume-failed", "无法从 {path} 继续游戏：{error}"),
+    ("record-failed", "无法写入回放文件 {path}：{error}"),
+    ("replay-read-failed", "无法读取回放文件 {path}：{error}"),
+    ("leaderboard-read-failed", "无法读取排行榜 {path}：{error}"),
+    ("leaderboard-write-failed", "无法写入排行榜 {path}：{error}"),
+    ("server-failed", "无法在 {addr} 运行服务器：{error}"),
+];
+
+fn catalog(lang: Lang) -> &'static [(&'static str, &'static str)] {
+    match lang {
+        Lang::En => EN,
+        Lang::ZhCn => ZH_CN,
+    }
+}
+
+fn lookup(lang: Lang, id: &str) -> Option<&'static str> {
+    catalog(lang)
+        .iter()
+        .find(|(key, _)| *key == id)
+        .map(|(_, text)| *text)
+}
+
+// Falls back to English for a message that has not been translated yet, and to the id
+// itself for one that does not exist at all, so a typo shows up on screen instead of a panic.
+pub fn text(lang: Lang, id: &str) -> String {
+    lookup(lang, id)
+        .or_else(|| lookup(Lang::En, id))
+        .unwrap_or(id)
+        .to_string()
+}
+
+// Looks up a message and replaces each `{name}` with its argument.
+pub fn message(lang: Lang, id: &str, args: &[(&str, &dyn fmt::Display)]) -> String {
+    let mut text = text(lang, id);
+    for (name, value) in args {
+        text = text.replace(&format!("{{{name}}}"), &value.to_string());
+    }
+    text
+}
+
+// The English message ids that `lang` has no translation for.
+pub fn untranslated(lang: Lang) -> Vec<&'static str> {
+    EN.iter()
+        .map(|(id, _)| *id)
+        .filter(|id| lookup(lang, id).is_none())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn placeholders(text: &str) -> Vec<&str> {
+        let mut names: Vec<&str> = text
+            .split('{')
+            .skip(1)
+            .filter_map(|rest| rest.split_once('}').map(|(name, _)| name))
+            .collect();
+        names.sort_unstable();
+        names
+    }
+
+    #[test]
+    fn chinese_is_complete() {
+        assert_eq!(untranslated(Lang::ZhCn), Vec::<&str>::new());
+        assert_eq!(EN.len(), ZH_CN.len());
+    }
+
+    #[test]
+    fn translations_use_the_same_placeholders() {
+        for (id, english) in EN {
+            let chinese = lookup(Lang::ZhCn, id).unwrap();
+            assert_eq!(placeholders(english), placeholders(chinese), "{id}");
+        }
+    }
+
+    #[test]
+    fn fills_in_placeholders() {
+        let guess = 42;
+        assert_eq!(
+            message(Lang::En, "you-guessed", &[("guess", &guess)]),
+            "You guessed 42"
+        );
+        assert_eq!(
+            message(Lang::ZhCn, "you-guessed", &[("guess", &guess)]),
+            "你猜的是 42"
+        );
+    }
+
+    #[test]
+    fn missing_messages_fall_back() {
+        assert_eq!(text(Lang::ZhCn, "no-such-message"), "no-such-message");
+        assert_eq!("zh-CN".parse(), Ok(Lang::ZhCn));
+        assert!("fr".parse::<Lang>().is_err());
+    }
+
+    // Every other file of the game, to check what it prints.
+    const SOURCES: &[(&str, &str)] = &[
+        ("bulls.rs", include_str!("bulls.rs")),
+        ("config.rs", include_str!("config.rs")),
+        ("game.rs", include_str!("game.rs")),
+        ("hints.rs", include_str!("hints.rs")),
+        ("leaderboard.rs", include_str!("leaderboard.rs")),
+        ("liar.rs", include_str!("liar.rs")),
+        ("main.rs", include_str!("main.rs")),
+        ("replay.rs", include_str!("replay.rs")),
+        ("reverse.rs", include_str!("reverse.rs")),
+        ("save.rs", include_str!("save.rs")),
+        ("server.rs", include_str!("server.rs")),
+        ("settings.rs", include_str!("settings.rs")),
+        ("strategy.rs", include_str!("strategy.rs")),
+        ("timer.rs", include_str!("timer.rs")),
+    ];
+
+    // The catalog comparison above cannot see a message that never made it into the
+    // catalog, so this looks at the format strings handed to the printing macros instead:
+    // apart from placeholders they may only hold punctuation, never words.
+    #[test]
+    fn printed_text_comes_from_the_catalog() {
+        for (file, source) in SOURCES {
+            for (at, _) in source
+                .match_indices("print!(")
+                .chain(source.match_indices("println!("))
+            {
+                let args = source[at..].split_once('(').unwrap().1.trim_start();
+                let Some(literal) = args
+                    .strip_prefix('"')
+                    .and_then(|rest| rest.split('"').next())
+                else {
+                    continue;
+                };
+                let mut depth = 0;
+                let outside: String = literal
+                    .chars()
+                    .filter(|&c| {
+                        match c {
+                            '{' => depth += 1,
+                            '}' => depth -= 1,
+                            _ => return depth == 0,
+                        }
+                        false
+                    })
+                    .collect();
+                assert!(
+                    !outside.chars().any(char::is_alphabetic),
+                    "{file} prints untranslated text: \"{literal}\""
+                );
+            }
+        }
+    }
+}
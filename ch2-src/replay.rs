@@ -0,0 +1,158 @@
+use crate::game::{GuessingGame, Number, Outcome};
+use rand::{rngs::StdRng, SeedableRng};
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::ops::RangeInclusive;
+
+// Everything needed to reproduce a game exactly: the seed fixes the secret number,
+// and the recorded guesses and outcomes are what the player saw.
+//
+// The file format is one record per line:
+//     seed 1234
+//     range 1 100
+//     guess 50 too-small
+//     guess 75 win
+#[derive(Debug, PartialEq)]
+pub struct Session {
+    pub seed: u64,
+    pub range: RangeInclusive<Number>,
+    pub guesses: Vec<(Number, Outcome)>,
+}
+
+// The generator behind `--seed`. Keeping it in one place means recording and playback
+// can never disagree about how a seed turns into a secret number.
+pub fn seeded_rng(seed: u64) -> StdRng {
+    StdRng::seed_from_u64(seed)
+}
+
+impl Session {
+    pub fn from_game(seed: u64, game: &GuessingGame) -> Session {
+        Session {
+            seed,
+            range: game.range().clone(),
+            guesses: game.history().to_vec(),
+        }
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), io::Error> {
+        fs::write(path, self.to_string())
+    }
+
+    pub fn load(path: &str) -> Result<Session, io::Error> {
+        fs::read_to_string(path)?.parse()
+    }
+
+    // Plays the recorded guesses against a fresh game built from the same seed.
+    // Returns the game so the caller can inspect it, or an error naming the first
+    // guess whose outcome differs from the recording.
+    pub fn replay(&self) -> Result<GuessingGame, String> {
+        let mut game = GuessingGame::with_rng(&mut seeded_rng(self.seed), self.range.clone());
+
+        for (number, &(guess, recorded)) in self.guesses.iter().enumerate() {
+            let outcome = game.submit(guess);
+            if outcome != recorded {
+                return Err(format!(
+                    "guess #{} ({guess}) was recorded as {recorded:?} but replays as {outcome:?}",
+                    number + 1
+                ));
+            }
+        }
+
+        Ok(game)
+    }
+}
+
+impl std::fmt::Display for Session {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "seed {}", self.seed)?;
+        writeln!(f, "range {} {}", self.range.start(), self.range.end())?;
+        for (guess, outcome) in &self.guesses {
+            let outcome = match outcome {
+                Outcome::TooSmall => "too-small",
+                Outcome::TooLarge => "too-large",
+                Outcome::Win => "win",
+            };
+            writeln!(f, "guess {guess} {outcome}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for Session {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Session, io::Error> {
+        let invalid = |line: &str| {
+            io::Error::new(ErrorKind::InvalidData, format!("bad replay line: `{line}`"))
+        };
+
+        let mut seed = None;
+        let mut range = None;
+        let mut guesses = Vec::new();
+
+        for line in s.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields[..] {
+                ["seed", value] => seed = Some(value.parse().map_err(|_| invalid(line))?),
+                ["range", low, high] => {
+                    let low = low.parse().map_err(|_| invalid(line))?;
+                    let high = high.parse().map_err(|_| invalid(line))?;
+                    if low > high {
+                        return Err(invalid(line));
+                    }
+                    range = Some(low..=high);
+                }
+                ["guess", guess, outcome] => {
+                    let guess = guess.parse().map_err(|_| invalid(line))?;
+                    let outcome = match outcome {
+                        "too-small" => Outcome::TooSmall,
+                        "too-large" => Outcome::TooLarge,
+                        "win" => Outcome::Win,
+                        _ => return Err(invalid(line)),
+                    };
+                    guesses.push((guess, outcome));
+                }
+                _ => return Err(invalid(line)),
+            }
+        }
+
+        match (seed, range) {
+            (Some(seed), Some(range)) => Ok(Session {
+                seed,
+                range,
+                guesses,
+            }),
+            _ => Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "replay file needs both a `seed` and a `range` line",
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn session_round_trips_through_text() {
+        let mut game = GuessingGame::with_rng(&mut seeded_rng(99), 1..=100);
+        game.submit(50);
+        game.submit(25);
+        let session = Session::from_game(99, &game);
+
+        let parsed: Session = session.to_string().parse().unwrap();
+        assert_eq!(parsed, session);
+        assert_eq!(parsed.replay().unwrap().history(), game.history());
+    }
+
+    #[test]
+    fn replay_detects_a_tampered_outcome() {
+        let session = Session {
+            seed: 1,
+            range: 1..=100,
+            guesses: vec![(0, Outcome::Win)],
+        };
+        assert!(session.replay().is_err());
+    }
+}
@@ -0,0 +1,218 @@
+use crate::game::{Number, Outcome};
+use crate::messages::{message, Lang};
+use std::fmt;
+use std::ops::RangeInclusive;
+
+// The reverse game: the player thinks of a number and the program guesses it by bisection.
+// A reply is stored as the same `Outcome` the normal game produces, so "higher" means our
+// guess was `TooSmall` and "lower" means it was `TooLarge`.
+#[derive(Debug)]
+pub struct ReverseGame {
+    range: RangeInclusive<Number>,
+    // Every number the replies so far still allow.
+    low: Number,
+    high: Number,
+    replies: Vec<(Number, Outcome)>,
+}
+
+// The player's replies could not all be true.
+#[derive(Debug, PartialEq)]
+pub struct Contradiction {
+    pub reply: (Number, Outcome),
+    // The earlier reply it conflicts with, or `None` when it conflicts with the range itself.
+    pub earlier: Option<(usize, Number, Outcome)>,
+    pub range: RangeInclusive<Number>,
+}
+
+pub fn parse_reply(reply: &str) -> Option<Outcome> {
+    match reply.trim().to_lowercase().as_str() {
+        "higher" | "h" => Some(Outcome::TooSmall),
+        "lower" | "l" => Some(Outcome::TooLarge),
+        "correct" | "c" => Some(Outcome::Win),
+        _ => None,
+    }
+}
+
+fn describe(lang: Lang, guess: Number, outcome: Outcome) -> String {
+    let id = match outcome {
+        Outcome::TooSmall => "number-higher",
+        Outcome::TooLarge => "number-lower",
+        Outcome::Win => "number-equal",
+    };
+    message(lang, id, &[("guess", &guess)])
+}
+
+impl ReverseGame {
+    pub fn new(range: RangeInclusive<Number>) -> ReverseGame {
+        ReverseGame {
+            low: *range.start(),
+            high: *range.end(),
+            range,
+            replies: Vec::new(),
+        }
+    }
+
+    // The middle of the numbers that are still possible. The sum is taken in `i128`
+    // so that even the full `i64` range cannot overflow.
+    pub fn next_guess(&self) -> Number {
+        ((self.low as i128 + self.high as i128).div_euclid(2)) as Number
+    }
+
+    pub fn replies(&self) -> &[(Number, Outcome)] {
+        &self.replies
+    }
+
+    pub fn reply(&mut self, guess: Number, outcome: Outcome) -> Result<(), Contradiction> {
+        match outcome {
+            Outcome::TooSmall if guess < self.high => self.low = self.low.max(guess + 1),
+            Outcome::TooLarge if guess > self.low => self.high = self.high.min(guess - 1),
+            Outcome::Win if (self.low..=self.high).contains(&guess) => {
+                self.low = guess;
+                self.high = guess;
+            }
+            _ => return Err(self.contradiction(guess, outcome)),
+        }
+
+        self.replies.push((guess, outcome));
+        Ok(())
+    }
+
+    // Finds the earliest reply that cannot be true at the same time as the new one.
+    // The comparison is the one the normal game uses: a number fits a reply when
+    // comparing the guess against it gives the same `Outcome`.
+    //
+    // Every reply describes an interval of numbers, and when intervals on a line have
+    // no number in common, two of them already have nothing in common. The earlier
+    // replies agree with each other, so the culprit pairs the new reply with either
+    // the range or one earlier reply.
+    fn contradiction(&self, guess: Number, outcome: Outcome) -> Contradiction {
+        let fits = |n: Number, (g, o): (Number, Outcome)| Outcome::from(g.cmp(&n)) == o;
+        let new = (guess, outcome);
+
+        let fits_range = match outcome {
+            Outcome::TooSmall => guess < *self.range.end(),
+            Outcome::TooLarge => guess > *self.range.start(),
+            Outcome::Win => self.range.contains(&guess),
+        };
+
+        let earlier = if fits_range {
+            self.replies
+                .iter()
+                .enumerate()
+                .find(|&(_, &earlier)| {
+                    // If two replies overlap, they share one of the guesses or a neighbour of one.
+                    ![earlier.0, guess]
+                        .into_iter()
+                        .flat_map(|g| [g.saturating_sub(1), g, g.saturating_add(1)])
+                        .any(|n| fits(n, earlier) && fits(n, new))
+                })
+                .map(|(index, &(g, o))| (index, g, o))
+        } else {
+            None
+        };
+
+        Contradiction {
+            reply: new,
+            earlier,
+            range: self.range.clone(),
+        }
+    }
+}
+
+impl Contradiction {
+    pub fn message(&self, lang: Lang) -> String {
+        let (guess, outcome) = self.reply;
+        let now = describe(lang, guess, outcome);
+        match self.earlier {
+            Some((index, g, o)) => message(
+                lang,
+                "contradiction-reply",
+                &[
+                    ("now", &now),
+                    ("index", &(index + 1)),
+                    ("then", &describe(lang, g, o)),
+                ],
+            ),
+            None => message(
+                lang,
+                "contradiction-range",
+                &[
+                    ("now", &now),
+                    ("low", self.range.start()),
+                    ("high", self.range.end()),
+                ],
+            ),
+        }
+    }
+}
+
+impl fmt::Display for Contradiction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message(Lang::En))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Answers every guess honestly for `secret`.
+    fn solve(secret: Number, range: RangeInclusive<Number>) -> usize {
+        let mut game = ReverseGame::new(range);
+        loop {
+            let guess = game.next_guess();
+            let outcome = Outcome::from(guess.cmp(&secret));
+            game.reply(guess, outcome).unwrap();
+            if outcome == Outcome::Win {
+                return game.replies().len();
+            }
+        }
+    }
+
+    #[test]
+    fn finds_every_number_within_seven_guesses() {
+        for secret in 1..=100 {
+            assert!(solve(secret, 1..=100) <= 7);
+        }
+    }
+
+    #[test]
+    fn handles_the_full_i64_range() {
+        assert!(solve(Number::MIN, Number::MIN..=Number::MAX) <= 65);
+        assert!(solve(Number::MAX, Number::MIN..=Number::MAX) <= 65);
+    }
+
+    #[test]
+    fn names_the_contradicting_reply() {
+        let mut game = ReverseGame::new(1..=100);
+        game.reply(50, Outcome::TooSmall).unwrap();
+        game.reply(75, Outcome::TooLarge).unwrap();
+        game.reply(62, Outcome::TooSmall).unwrap();
+
+        let contradiction = game.reply(40, Outcome::TooLarge).unwrap_err();
+        assert_eq!(contradiction.earlier, Some((0, 50, Outcome::TooSmall)));
+        assert_eq!(
+            contradiction.to_string(),
+            "You said the number is lower than 40, but reply #1 said it is higher than 50."
+        );
+    }
+
+    #[test]
+    fn contradiction_with_the_range() {
+        let mut game = ReverseGame::new(1..=100);
+        let contradiction = game.reply(100, Outcome::TooSmall).unwrap_err();
+        assert_eq!(contradiction.earlier, None);
+        assert_eq!(
+            contradiction.message(Lang::ZhCn),
+            "你说这个数字大于 100，但它必须在 1 和 100 之间。"
+        );
+    }
+
+    #[test]
+    fn parses_replies() {
+        assert_eq!(parse_reply(" Higher\n"), Some(Outcome::TooSmall));
+        assert_eq!(parse_reply("l"), Some(Outcome::TooLarge));
+        assert_eq!(parse_reply("correct"), Some(Outcome::Win));
+        assert_eq!(parse_reply("maybe"), None);
+    }
+}
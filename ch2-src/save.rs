@@ -0,0 +1,244 @@
+use crate::game::{GuessingGame, Number};
+use rand::Rng;
+use std::fmt::Write;
+use std::fs;
+use std::io::{self, ErrorKind};
+
+// A saved game in progress. The file is plain text so it is easy to inspect, but the secret
+// number is masked and every line is covered by a keyed checksum:
+//     version 1
+//     nonce 9f3c0d2a71b4e655
+//     range 1 100
+//     attempts 7
+//     secret 5e0c3b1a99f2d7c4
+//     guess 50
+//     mac 0b6e41f2c39a8d17
+//
+// The key ships inside the program, so this stops a player from simply editing the file to
+// read or change the secret, not someone willing to reverse engineer the program.
+const KEY: (u64, u64) = (0x6775_6573_735f_6761, 0x6d65_5f73_6176_6531);
+
+pub const DEFAULT_PATH: &str = "guessing_game.sav";
+
+// The file a line typed during the game asks to save to: `save` on its own uses the
+// default file and `save <file>` names one. Anything else, such as `saved`, is not a
+// save command and gives `None`.
+pub fn save_command(line: &str) -> Option<&str> {
+    let (word, path) = line
+        .trim()
+        .split_once(char::is_whitespace)
+        .unwrap_or((line.trim(), ""));
+    match (word, path.trim()) {
+        ("save", "") => Some(DEFAULT_PATH),
+        ("save", path) => Some(path),
+        _ => None,
+    }
+}
+
+pub fn save(game: &GuessingGame, path: &str) -> Result<(), io::Error> {
+    let nonce: u64 = rand::thread_rng().gen();
+    fs::write(path, encode(game, nonce))
+}
+
+pub fn load(path: &str) -> Result<GuessingGame, io::Error> {
+    decode(&fs::read_to_string(path)?)
+}
+
+fn encode(game: &GuessingGame, nonce: u64) -> String {
+    let mut body = String::new();
+    let _ = writeln!(body, "version 1");
+    let _ = writeln!(body, "nonce {nonce:016x}");
+    let _ = writeln!(
+        body,
+        "range {} {}",
+        game.range().start(),
+        game.range().end()
+    );
+    let attempts = game
+        .max_attempts()
+        .map_or(String::from("none"), |max| max.to_string());
+    let _ = writeln!(body, "attempts {attempts}");
+    let _ = writeln!(body, "secret {:016x}", mask(game.secret(), nonce));
+    for (guess, _) in game.history() {
+        let _ = writeln!(body, "guess {guess}");
+    }
+    let mac = siphash(KEY, body.as_bytes());
+    let _ = writeln!(body, "mac {mac:016x}");
+    body
+}
+
+fn decode(contents: &str) -> Result<GuessingGame, io::Error> {
+    let invalid = |message: &str| io::Error::new(ErrorKind::InvalidData, message.to_string());
+
+    // Everything before the `mac` line is what the checksum covers.
+    let mac_at = contents
+        .rfind("mac ")
+        .ok_or_else(|| invalid("save file has no checksum"))?;
+    let (body, mac_line) = contents.split_at(mac_at);
+    let mac = u64::from_str_radix(mac_line["mac ".len()..].trim(), 16)
+        .map_err(|_| invalid("save file has a malformed checksum"))?;
+    if siphash(KEY, body.as_bytes()) != mac {
+        return Err(invalid("save file has been modified or is damaged"));
+    }
+
+    let mut nonce = None;
+    let mut range = None;
+    let mut max_attempts = None;
+    let mut masked = None;
+    let mut guesses = Vec::new();
+
+    for line in body.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields[..] {
+            ["version", "1"] => {}
+            ["nonce", value] => nonce = u64::from_str_radix(value, 16).ok(),
+            ["range", low, high] => {
+                range = low.parse::<Number>().ok().zip(high.parse::<Number>().ok());
+            }
+            ["attempts", "none"] => {}
+            ["attempts", value] => max_attempts = value.parse().ok(),
+            ["secret", value] => masked = u64::from_str_radix(value, 16).ok(),
+            ["guess", value] => guesses.push(
+                value
+                    .parse()
+                    .map_err(|_| invalid("save file has a malformed guess"))?,
+            ),
+            _ => return Err(invalid("save file has an unknown line")),
+        }
+    }
+
+    let (Some(nonce), Some((low, high)), Some(masked)) = (nonce, range, masked) else {
+        return Err(invalid("save file is missing its nonce, range or secret"));
+    };
+    let secret = unmask(masked, nonce);
+    if low > high || secret < low || secret > high {
+        return Err(invalid("save file does not describe a valid game"));
+    }
+
+    // Outcomes are not stored; replaying the guesses recomputes them from the secret.
+    let mut game = GuessingGame::new(secret, low..=high).with_max_attempts(max_attempts);
+    for guess in guesses {
+        game.submit(guess);
+    }
+    Ok(game)
+}
+
+// The secret is XORed with a value derived from the nonce, so the same secret
+// looks different in every save.
+fn mask(secret: Number, nonce: u64) -> u64 {
+    (secret as u64) ^ siphash(KEY, &nonce.to_le_bytes())
+}
+
+fn unmask(masked: u64, nonce: u64) -> Number {
+    (masked ^ siphash(KEY, &nonce.to_le_bytes())) as Number
+}
+
+// SipHash-2-4, written out here because the standard library's hasher is allowed to change
+// between Rust releases and a save file has to stay readable.
+fn siphash(key: (u64, u64), data: &[u8]) -> u64 {
+    let mut v = [
+        key.0 ^ 0x736f_6d65_7073_6575,
+        key.1 ^ 0x646f_7261_6e64_6f6d,
+        key.0 ^ 0x6c79_6765_6e65_7261,
+        key.1 ^ 0x7465_6462_7974_6573,
+    ];
+
+    fn round(v: &mut [u64; 4]) {
+        v[0] = v[0].wrapping_add(v[1]);
+        v[1] = v[1].rotate_left(13) ^ v[0];
+        v[0] = v[0].rotate_left(32);
+        v[2] = v[2].wrapping_add(v[3]);
+        v[3] = v[3].rotate_left(16) ^ v[2];
+        v[0] = v[0].wrapping_add(v[3]);
+        v[3] = v[3].rotate_left(21) ^ v[0];
+        v[2] = v[2].wrapping_add(v[1]);
+        v[1] = v[1].rotate_left(17) ^ v[2];
+        v[2] = v[2].rotate_left(32);
+    }
+
+    let mut compress = |m: u64| {
+        v[3] ^= m;
+        round(&mut v);
+        round(&mut v);
+        v[0] ^= m;
+    };
+
+    let mut chunks = data.chunks_exact(8);
+    for chunk in &mut chunks {
+        compress(u64::from_le_bytes(chunk.try_into().unwrap()));
+    }
+    let mut last = [0u8; 8];
+    last[..chunks.remainder().len()].copy_from_slice(chunks.remainder());
+    last[7] = data.len() as u8;
+    compress(u64::from_le_bytes(last));
+
+    v[2] ^= 0xff;
+    for _ in 0..4 {
+        round(&mut v);
+    }
+    v[0] ^ v[1] ^ v[2] ^ v[3]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn siphash_reference_vector() {
+        // From the SipHash paper: key 00 01 .. 0f, message 00 01 .. 0e.
+        let key = (0x0706_0504_0302_0100, 0x0f0e_0d0c_0b0a_0908);
+        let message: Vec<u8> = (0..15).collect();
+        assert_eq!(siphash(key, &message), 0xa129_ca61_49be_45e5);
+    }
+
+    #[test]
+    fn only_the_word_save_is_a_command() {
+        assert_eq!(save_command("save"), Some(DEFAULT_PATH));
+        assert_eq!(save_command("  save  game.sav "), Some("game.sav"));
+        assert_eq!(save_command("save my game.sav"), Some("my game.sav"));
+        assert_eq!(save_command("saved"), None);
+        assert_eq!(save_command("savegame.sav"), None);
+        assert_eq!(save_command("50"), None);
+    }
+
+    #[test]
+    fn save_round_trips() {
+        let mut game = GuessingGame::new(-42, -100..=100).with_max_attempts(Some(9));
+        game.submit(0);
+        game.submit(-50);
+
+        let restored = decode(&encode(&game, 12345)).unwrap();
+        assert_eq!(restored.secret(), -42);
+        assert_eq!(restored.range(), game.range());
+        assert_eq!(restored.max_attempts(), Some(9));
+        assert_eq!(restored.history(), game.history());
+    }
+
+    #[test]
+    fn secret_is_not_stored_in_plain_text() {
+        let game = GuessingGame::new(42, 1..=100);
+        let saved = encode(&game, 1);
+        assert!(!saved.contains("secret 42"));
+        assert!(!saved.contains("000000000000002a"));
+    }
+
+    #[test]
+    fn edited_files_are_rejected() {
+        let mut game = GuessingGame::new(42, 1..=100);
+        game.submit(10);
+        let saved = encode(&game, 7);
+
+        for (from, to) in [("range 1 100", "range 1 50"), ("guess 10", "guess 11")] {
+            let edited = saved.replace(from, to);
+            assert_ne!(edited, saved);
+            assert!(decode(&edited).is_err(), "{from} -> {to}");
+        }
+
+        let secret_line = saved
+            .lines()
+            .find(|line| line.starts_with("secret"))
+            .unwrap();
+        let edited = saved.replace(secret_line, "secret 0000000000000000");
+        assert!(decode(&edited).is_err());
+    }
+}
@@ -0,0 +1,376 @@
+use crate::game::{GuessingGame, Number, Outcome};
+use crate::messages::{message, Lang};
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, BufReader, ErrorKind, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+// A multiplayer race over TCP. Every player guesses the same secret number and sees
+// everybody's results, and the first correct guess wins.
+//
+// The protocol is one command per line. Clients send:
+//     NAME <name>     choose the name the others see
+//     GUESS <number>  guess the secret number
+//     QUIT            leave the game
+// and the server sends:
+//     WELCOME <name> <low> <high>
+//     JOINED <name>
+//     RESULT <name> <number> too-small|too-large|win
+//     WINNER <name> <number>
+//     LEFT <name> <reason>
+//     ERROR <message>
+// The words in capitals are for programs and stay the same in every language; only the
+// text of an ERROR is meant for people, so it comes from the message catalog.
+
+// Like the web server in chapter 20, each connection gets its own thread. Those threads
+// only read lines and pass them over a channel, so the game itself lives on one thread
+// and never needs a lock.
+enum Event {
+    Joined { id: usize, stream: TcpStream },
+    Line { id: usize, line: String },
+    Left { id: usize, reason: &'static str },
+}
+
+struct Player {
+    name: String,
+    stream: TcpStream,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Winner {
+    pub name: String,
+    pub guesses: usize,
+}
+
+// Runs one game on `listener` until somebody wins. Players that send nothing for
+// `idle_timeout` are disconnected, as are players whose connection breaks.
+pub fn serve(
+    listener: TcpListener,
+    mut game: GuessingGame,
+    idle_timeout: Duration,
+    lang: Lang,
+) -> Result<Winner, io::Error> {
+    listener.set_nonblocking(true)?;
+    let (tx, rx) = mpsc::channel();
+    let stop = Arc::new(AtomicBool::new(false));
+
+    let accept_stop = Arc::clone(&stop);
+    let accepter = thread::spawn(move || accept(listener, tx, idle_timeout, accept_stop));
+
+    let mut players: BTreeMap<usize, Player> = BTreeMap::new();
+    let (low, high) = (*game.range().start(), *game.range().end());
+
+    let winner = loop {
+        let Ok(event) = rx.recv() else {
+            return Err(io::Error::other("stopped accepting players"));
+        };
+
+        match event {
+            Event::Joined { id, stream } => {
+                let player = Player {
+                    name: format!("player{id}"),
+                    stream,
+                };
+                let welcome = format!("WELCOME {} {low} {high}", player.name);
+                let joined = format!("JOINED {}", player.name);
+                players.insert(id, player);
+                send(&mut players, id, &welcome);
+                broadcast(&mut players, &joined);
+            }
+            Event::Line { id, line } => {
+                if let Some(winner) = handle(&mut game, &mut players, id, line.trim(), lang) {
+                    break winner;
+                }
+            }
+            Event::Left { id, reason } => leave(&mut players, id, reason),
+        }
+    };
+
+    stop.store(true, Ordering::SeqCst);
+    for player in players.values() {
+        let _ = player.stream.shutdown(Shutdown::Both);
+    }
+    let _ = accepter.join();
+    Ok(winner)
+}
+
+fn accept(
+    listener: TcpListener,
+    tx: mpsc::Sender<Event>,
+    idle_timeout: Duration,
+    stop: Arc<AtomicBool>,
+) {
+    let mut next_id = 1;
+    // The listener is non-blocking so this loop can notice when the game is over.
+    while !stop.load(Ordering::SeqCst) {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                let id = next_id;
+                next_id += 1;
+                // A client that stops reading must not be able to stall the whole game,
+                // so writes to it give up after the idle timeout too.
+                let Ok(writer) = stream
+                    .set_nonblocking(false)
+                    .and_then(|_| stream.set_write_timeout(Some(idle_timeout)))
+                    .and_then(|_| stream.try_clone())
+                else {
+                    continue;
+                };
+                if tx.send(Event::Joined { id, stream: writer }).is_err() {
+                    return;
+                }
+                let tx = tx.clone();
+                thread::spawn(move || read_lines(id, stream, tx, idle_timeout));
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => thread::sleep(Duration::from_millis(10)),
+            Err(_) => return,
+        }
+    }
+}
+
+fn read_lines(id: usize, stream: TcpStream, tx: mpsc::Sender<Event>, idle_timeout: Duration) {
+    if stream.set_read_timeout(Some(idle_timeout)).is_err() {
+        let _ = tx.send(Event::Left {
+            id,
+            reason: "disconnected",
+        });
+        return;
+    }
+
+    let mut reader = BufReader::new(stream);
+    loop {
+        let mut line = String::new();
+        let reason = match reader.read_line(&mut line) {
+            Ok(0) => "disconnected",
+            Ok(_) => {
+                if tx.send(Event::Line { id, line }).is_err() {
+                    return;
+                }
+                continue;
+            }
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => "idle",
+            Err(_) => "disconnected",
+        };
+        let _ = tx.send(Event::Left { id, reason });
+        return;
+    }
+}
+
+fn handle(
+    game: &mut GuessingGame,
+    players: &mut BTreeMap<usize, Player>,
+    id: usize,
+    line: &str,
+    lang: Lang,
+) -> Option<Winner> {
+    let name = players.get(&id)?.name.clone();
+    let mut words = line.split_whitespace();
+
+    match (words.next(), words.next(), words.next()) {
+        (Some("NAME"), Some(new_name), None) => {
+            // Names are how players tell each other apart, so each one belongs to a single
+            // player. The `player<id>` names are kept for the players still to come.
+            let taken = players
+                .iter()
+                .any(|(&other, player)| other != id && player.name == new_name)
+                || new_name
+                    .strip_prefix("player")
+                    .is_some_and(|number| number.parse::<usize>().is_ok() && new_name != name);
+            if taken {
+                let error = message(lang, "server-name-taken", &[("name", &new_name)]);
+                send(players, id, &format!("ERROR {error}"));
+                return None;
+            }
+            players.get_mut(&id)?.name = new_name.to_string();
+            broadcast(players, &format!("JOINED {new_name}"));
+        }
+        (Some("GUESS"), Some(number), None) => match number.parse::<Number>() {
+            Ok(guess) if !game.range().contains(&guess) => {
+                let (low, high) = (game.range().start(), game.range().end());
+                let error = message(
+                    lang,
+                    "server-out-of-range",
+                    &[("guess", &guess), ("low", low), ("high", high)],
+                );
+                send(players, id, &format!("ERROR {error}"));
+            }
+            Ok(guess) => {
+                let outcome = game.submit(guess);
+                let result = match outcome {
+                    Outcome::TooSmall => "too-small",
+                    Outcome::TooLarge => "too-large",
+                    Outcome::Win => "win",
+                };
+                broadcast(players, &format!("RESULT {name} {guess} {result}"));
+                if outcome == Outcome::Win {
+                    broadcast(players, &format!("WINNER {name} {guess}"));
+                    return Some(Winner {
+                        name,
+                        guesses: game.history().len(),
+                    });
+                }
+            }
+            Err(_) => {
+                let error = message(lang, "server-not-a-number", &[("number", &number)]);
+                send(players, id, &format!("ERROR {error}"));
+            }
+        },
+        (Some("QUIT"), None, None) => leave(players, id, "quit"),
+        _ => {
+            let error = message(lang, "server-unknown-command", &[("line", &line)]);
+            send(players, id, &format!("ERROR {error}"));
+        }
+    }
+    None
+}
+
+fn send(players: &mut BTreeMap<usize, Player>, id: usize, message: &str) {
+    let failed = match players.get_mut(&id) {
+        Some(player) => writeln!(player.stream, "{message}").is_err(),
+        None => false,
+    };
+    if failed {
+        leave(players, id, "disconnected");
+    }
+}
+
+// Sends a line to every player. Anyone whose connection has gone away is dropped,
+// and the others are told about it.
+fn broadcast(players: &mut BTreeMap<usize, Player>, message: &str) {
+    let failed: Vec<usize> = players
+        .iter_mut()
+        .filter_map(|(&id, player)| writeln!(player.stream, "{message}").err().map(|_| id))
+        .collect();
+    for id in failed {
+        leave(players, id, "disconnected");
+    }
+}
+
+fn leave(players: &mut BTreeMap<usize, Player>, id: usize, reason: &str) {
+    if let Some(player) = players.remove(&id) {
+        let _ = player.stream.shutdown(Shutdown::Both);
+        broadcast(players, &format!("LEFT {} {reason}", player.name));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Client {
+        reader: BufReader<TcpStream>,
+        writer: TcpStream,
+    }
+
+    impl Client {
+        fn connect(port: u16) -> Client {
+            let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+            stream
+                .set_read_timeout(Some(Duration::from_secs(5)))
+                .unwrap();
+            Client {
+                writer: stream.try_clone().unwrap(),
+                reader: BufReader::new(stream),
+            }
+        }
+
+        fn send(&mut self, line: &str) {
+            writeln!(self.writer, "{line}").unwrap();
+        }
+
+        // Reads lines until one starts with `prefix`, so tests do not depend on
+        // exactly when other players' JOINED messages arrive.
+        fn expect(&mut self, prefix: &str) -> String {
+            loop {
+                let mut line = String::new();
+                assert!(
+                    self.reader.read_line(&mut line).unwrap() > 0,
+                    "closed before `{prefix}`"
+                );
+                if line.starts_with(prefix) {
+                    return line.trim().to_string();
+                }
+            }
+        }
+    }
+
+    fn start(secret: Number, idle: Duration) -> (u16, thread::JoinHandle<Winner>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let game = GuessingGame::new(secret, 1..=100);
+        let server = thread::spawn(move || serve(listener, game, idle, Lang::En).unwrap());
+        (port, server)
+    }
+
+    #[test]
+    fn players_race_for_the_same_secret() {
+        let (port, server) = start(42, Duration::from_secs(5));
+        let mut alice = Client::connect(port);
+        alice.expect("WELCOME");
+        alice.send("NAME alice");
+        alice.expect("JOINED alice");
+
+        let mut bob = Client::connect(port);
+        bob.expect("WELCOME");
+        bob.send("NAME bob");
+        alice.expect("JOINED bob");
+
+        alice.send("GUESS 50");
+        assert_eq!(bob.expect("RESULT"), "RESULT alice 50 too-large");
+        bob.send("GUESS abc");
+        assert_eq!(bob.expect("ERROR"), "ERROR `abc` is not a number");
+        bob.send("GUESS 500");
+        assert_eq!(bob.expect("ERROR"), "ERROR `500` is not between 1 and 100");
+        bob.send("NAME alice");
+        assert_eq!(bob.expect("ERROR"), "ERROR the name `alice` is taken");
+        bob.send("NAME player9");
+        assert_eq!(bob.expect("ERROR"), "ERROR the name `player9` is taken");
+        bob.send("GUESS 42");
+        assert_eq!(alice.expect("WINNER"), "WINNER bob 42");
+
+        assert_eq!(
+            server.join().unwrap(),
+            Winner {
+                name: String::from("bob"),
+                guesses: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn dropped_and_idle_players_are_removed() {
+        let (port, server) = start(7, Duration::from_secs(2));
+        let mut alice = Client::connect(port);
+        alice.expect("WELCOME");
+        alice.send("NAME alice");
+        alice.expect("JOINED alice");
+
+        let mut bob = Client::connect(port);
+        bob.expect("WELCOME");
+        drop(bob);
+        assert_eq!(alice.expect("LEFT"), "LEFT player2 disconnected");
+
+        let mut carol = Client::connect(port);
+        carol.expect("WELCOME");
+        carol.send("NAME carol");
+        // Keep alice busy while carol says nothing at all. Alice is never quiet for more
+        // than a small part of the idle timeout, so only carol can be dropped, however
+        // slowly this runs.
+        loop {
+            thread::sleep(Duration::from_millis(100));
+            alice.send("GUESS 1");
+            let line = alice.expect("");
+            if line.starts_with("LEFT") {
+                assert_eq!(line, "LEFT carol idle");
+                break;
+            }
+        }
+
+        alice.send("GUESS 7");
+        alice.expect("WINNER alice 7");
+        server.join().unwrap();
+    }
+}
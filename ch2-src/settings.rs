@@ -0,0 +1,122 @@
+use crate::game::Number;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+// Named presets that set both the range and the attempt limit.
+// Normal and hard allow exactly as many guesses as a binary search needs for their range;
+// easy leaves some room for mistakes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn settings(self) -> Settings {
+        match self {
+            Difficulty::Easy => Settings {
+                range: 1..=50,
+                max_attempts: Some(10),
+            },
+            Difficulty::Normal => Settings {
+                range: 1..=100,
+                max_attempts: Some(7),
+            },
+            Difficulty::Hard => Settings {
+                range: 1..=1000,
+                max_attempts: Some(10),
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let name = match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Difficulty, String> {
+        match s {
+            "easy" => Ok(Difficulty::Easy),
+            "normal" => Ok(Difficulty::Normal),
+            "hard" => Ok(Difficulty::Hard),
+            other => Err(format!(
+                "unknown difficulty `{other}`, expected easy, normal or hard"
+            )),
+        }
+    }
+}
+
+// The rules of one game. Like `Guess::new` in chapter 9, `Settings::new` is the only way
+// to build one from user input, so a `Settings` value always holds a usable range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Settings {
+    pub range: RangeInclusive<Number>,
+    pub max_attempts: Option<usize>,
+}
+
+impl Default for Settings {
+    // The original game: 1 to 100 with as many guesses as you like.
+    fn default() -> Settings {
+        Settings {
+            range: 1..=100,
+            max_attempts: None,
+        }
+    }
+}
+
+impl Settings {
+    pub fn new(low: Number, high: Number, max_attempts: Option<usize>) -> Result<Settings, String> {
+        if low > high {
+            return Err(format!(
+                "the range {low}..={high} is empty: the lower bound must not be greater than the upper bound"
+            ));
+        }
+        if max_attempts == Some(0) {
+            return Err(String::from(
+                "the maximum number of attempts must be at least 1",
+            ));
+        }
+
+        Ok(Settings {
+            range: low..=high,
+            max_attempts,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inverted_range_is_rejected() {
+        assert!(Settings::new(10, 1, None).is_err());
+        assert!(Settings::new(5, 5, None).is_ok());
+    }
+
+    #[test]
+    fn zero_attempts_is_rejected() {
+        assert!(Settings::new(1, 100, Some(0)).is_err());
+    }
+
+    #[test]
+    fn presets_can_be_won_by_binary_search() {
+        for difficulty in [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard] {
+            let settings = difficulty.settings();
+            let size = settings.range.end() - settings.range.start() + 1;
+            let needed = (size as f64).log2().floor() as usize + 1;
+            assert!(settings.max_attempts.unwrap() >= needed, "{difficulty:?}");
+        }
+    }
+}
@@ -0,0 +1,360 @@
+use crate::game::{self, Ending, Frontend, GuessingGame, Number, Outcome};
+use crate::settings::Settings;
+use rand::Rng;
+use std::fmt::Write;
+use std::ops::RangeInclusive;
+
+// An automated player. Bots only see the range and the outcome of their own guesses,
+// exactly like a human at the terminal.
+pub trait Strategy {
+    fn name(&self) -> &str;
+    // Called at the start of every game, so one bot can play many games in a row.
+    fn start(&mut self, range: &RangeInclusive<Number>);
+    fn next_guess(&mut self) -> Number;
+    fn learn(&mut self, guess: Number, outcome: Outcome);
+}
+
+// The numbers that are still possible, narrowed by every "Too small" or "Too large".
+#[derive(Debug, Default, Clone, Copy)]
+struct Interval {
+    low: Number,
+    high: Number,
+}
+
+impl Interval {
+    fn start(&mut self, range: &RangeInclusive<Number>) {
+        self.low = *range.start();
+        self.high = *range.end();
+    }
+
+    fn learn(&mut self, guess: Number, outcome: Outcome) {
+        match outcome {
+            Outcome::TooSmall => self.low = self.low.max(guess.saturating_add(1)),
+            Outcome::TooLarge => self.high = self.high.min(guess.saturating_sub(1)),
+            Outcome::Win => {}
+        }
+    }
+
+    // `low + fraction * (high - low)`, computed in `i128` so the full `i64` range cannot overflow.
+    fn point(&self, numerator: i128, denominator: i128) -> Number {
+        let (low, high) = (self.low as i128, self.high as i128);
+        (low + (high - low) * numerator / denominator) as Number
+    }
+}
+
+// Always guesses the middle of what is left.
+#[derive(Default)]
+pub struct BinarySearch {
+    interval: Interval,
+}
+
+impl Strategy for BinarySearch {
+    fn name(&self) -> &str {
+        "binary"
+    }
+
+    fn start(&mut self, range: &RangeInclusive<Number>) {
+        self.interval.start(range);
+    }
+
+    fn next_guess(&mut self) -> Number {
+        self.interval.point(1, 2)
+    }
+
+    fn learn(&mut self, guess: Number, outcome: Outcome) {
+        self.interval.learn(guess, outcome);
+    }
+}
+
+// Guesses any number that is still possible.
+pub struct RandomGuess<R: Rng> {
+    rng: R,
+    interval: Interval,
+}
+
+impl<R: Rng> RandomGuess<R> {
+    pub fn new(rng: R) -> RandomGuess<R> {
+        RandomGuess {
+            rng,
+            interval: Interval::default(),
+        }
+    }
+}
+
+impl<R: Rng> Strategy for RandomGuess<R> {
+    fn name(&self) -> &str {
+        "random"
+    }
+
+    fn start(&mut self, range: &RangeInclusive<Number>) {
+        self.interval.start(range);
+    }
+
+    fn next_guess(&mut self) -> Number {
+        self.rng.gen_range(self.interval.low..=self.interval.high)
+    }
+
+    fn learn(&mut self, guess: Number, outcome: Outcome) {
+        self.interval.learn(guess, outcome);
+    }
+}
+
+// Counts up from the bottom of the range, ignoring everything but the win.
+#[derive(Default)]
+pub struct Linear {
+    next: Number,
+}
+
+impl Strategy for Linear {
+    fn name(&self) -> &str {
+        "linear"
+    }
+
+    fn start(&mut self, range: &RangeInclusive<Number>) {
+        self.next = *range.start();
+    }
+
+    fn next_guess(&mut self) -> Number {
+        self.next
+    }
+
+    fn learn(&mut self, _guess: Number, _outcome: Outcome) {
+        self.next = self.next.saturating_add(1);
+    }
+}
+
+// Binary search always takes longest on the same few secrets, so an opponent who knows
+// the strategy can pick one of them every time. Splitting at a random point in the middle
+// third keeps close to the binary search average while leaving no fixed worst case to aim for.
+pub struct Hedged<R: Rng> {
+    rng: R,
+    interval: Interval,
+}
+
+impl<R: Rng> Hedged<R> {
+    pub fn new(rng: R) -> Hedged<R> {
+        Hedged {
+            rng,
+            interval: Interval::default(),
+        }
+    }
+}
+
+impl<R: Rng> Strategy for Hedged<R> {
+    fn name(&self) -> &str {
+        "hedged"
+    }
+
+    fn start(&mut self, range: &RangeInclusive<Number>) {
+        self.interval.start(range);
+    }
+
+    fn next_guess(&mut self) -> Number {
+        let low = self.interval.point(1, 3);
+        let high = self.interval.point(2, 3);
+        self.rng.gen_range(low..=high)
+    }
+
+    fn learn(&mut self, guess: Number, outcome: Outcome) {
+        self.interval.learn(guess, outcome);
+    }
+}
+
+// Lets any strategy drive the normal game engine through the `Frontend` trait.
+pub struct Bot<'a>(pub &'a mut dyn Strategy);
+
+impl Frontend for Bot<'_> {
+    fn start(&mut self, game: &GuessingGame) {
+        self.0.start(game.range());
+    }
+
+    fn read_guess(&mut self, _game: &GuessingGame) -> Option<Number> {
+        Some(self.0.next_guess())
+    }
+
+    fn report(&mut self, guess: Number, outcome: Outcome) {
+        self.0.learn(guess, outcome);
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Stats {
+    pub name: String,
+    pub games: usize,
+    pub wins: usize,
+    pub mean: f64,
+    pub median: f64,
+    pub worst: usize,
+}
+
+impl Stats {
+    fn from_counts(name: &str, mut counts: Vec<usize>, wins: usize) -> Stats {
+        counts.sort_unstable();
+        let games = counts.len();
+        let median = match games {
+            0 => 0.0,
+            n if n % 2 == 1 => counts[n / 2] as f64,
+            n => (counts[n / 2 - 1] + counts[n / 2]) as f64 / 2.0,
+        };
+
+        Stats {
+            name: name.to_string(),
+            games,
+            wins,
+            mean: counts.iter().sum::<usize>() as f64 / games.max(1) as f64,
+            median,
+            worst: counts.last().copied().unwrap_or(0),
+        }
+    }
+}
+
+// The most guesses a tournament game allows when the settings set no limit. A smaller
+// range gets as many guesses as it has numbers, which is enough for every strategy to win.
+pub const MAX_TOURNAMENT_ATTEMPTS: usize = 10_000;
+
+// Every guess is kept in the game history, so a game without a limit could fill memory
+// on a wide range, as `linear` would on 1..=100000000000.
+fn tournament_attempts(settings: &Settings) -> usize {
+    let (low, high) = (*settings.range.start(), *settings.range.end());
+    let size = usize::try_from(high.abs_diff(low)).unwrap_or(usize::MAX);
+    let cap = size.saturating_add(1).min(MAX_TOURNAMENT_ATTEMPTS);
+    settings.max_attempts.map_or(cap, |limit| limit.min(cap))
+}
+
+// Plays `games` games per strategy. Every strategy faces the same secrets, drawn from `rng`
+// the same way the real game draws them, so the numbers are directly comparable.
+// A game that runs out of attempts counts as a loss.
+pub fn tournament(
+    strategies: &mut [Box<dyn Strategy>],
+    settings: &Settings,
+    games: usize,
+    rng: &mut impl Rng,
+) -> Vec<Stats> {
+    let secrets: Vec<Number> = (0..games)
+        .map(|_| rng.gen_range(settings.range.clone()))
+        .collect();
+    let max_attempts = tournament_attempts(settings);
+
+    strategies
+        .iter_mut()
+        .map(|strategy| {
+            let mut counts = Vec::with_capacity(games);
+            let mut wins = 0;
+            for &secret in &secrets {
+                let mut game = GuessingGame::new(secret, settings.range.clone())
+                    .with_max_attempts(Some(max_attempts));
+                if let Ending::Won { .. } = game::play(&mut game, &mut Bot(strategy.as_mut())) {
+                    wins += 1;
+                }
+                counts.push(game.history().len());
+            }
+            Stats::from_counts(strategy.name(), counts, wins)
+        })
+        .collect()
+}
+
+pub fn table(stats: &[Stats]) -> String {
+    let mut out = format!(
+        "{:<10} {:>8} {:>8} {:>8} {:>8} {:>8}\n",
+        "strategy", "games", "wins", "mean", "median", "worst"
+    );
+    for s in stats {
+        let _ = writeln!(
+            out,
+            "{:<10} {:>8} {:>8} {:>8.2} {:>8.1} {:>8}",
+            s.name, s.games, s.wins, s.mean, s.median, s.worst
+        );
+    }
+    out
+}
+
+pub fn csv(stats: &[Stats]) -> String {
+    let mut out = String::from("strategy,games,wins,mean,median,worst\n");
+    for s in stats {
+        let _ = writeln!(
+            out,
+            "{},{},{},{:.4},{},{}",
+            s.name, s.games, s.wins, s.mean, s.median, s.worst
+        );
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn all_strategies() -> Vec<Box<dyn Strategy>> {
+        vec![
+            Box::new(BinarySearch::default()),
+            Box::new(RandomGuess::new(StdRng::seed_from_u64(1))),
+            Box::new(Linear::default()),
+            Box::new(Hedged::new(StdRng::seed_from_u64(2))),
+        ]
+    }
+
+    #[test]
+    fn every_strategy_always_wins_without_a_limit() {
+        let settings = Settings::default();
+        let stats = tournament(
+            &mut all_strategies(),
+            &settings,
+            2000,
+            &mut StdRng::seed_from_u64(3),
+        );
+
+        for s in &stats {
+            assert_eq!(s.wins, 2000, "{}", s.name);
+        }
+        assert_eq!(stats[0].worst, 7);
+        assert_eq!(stats[2].worst, 100);
+        assert!(stats[3].mean < stats[1].mean);
+    }
+
+    #[test]
+    fn binary_search_handles_the_full_range() {
+        let settings = Settings::new(Number::MIN, Number::MAX, Some(65)).unwrap();
+        let mut strategies: Vec<Box<dyn Strategy>> = vec![Box::new(BinarySearch::default())];
+        let stats = tournament(
+            &mut strategies,
+            &settings,
+            100,
+            &mut StdRng::seed_from_u64(4),
+        );
+        assert_eq!(stats[0].wins, 100);
+    }
+
+    #[test]
+    fn wide_ranges_cap_every_game() {
+        let settings = Settings::new(1, 100_000_000_000, None).unwrap();
+        let stats = tournament(
+            &mut all_strategies(),
+            &settings,
+            10,
+            &mut StdRng::seed_from_u64(5),
+        );
+
+        assert_eq!(stats[0].wins, 10);
+        assert_eq!(stats[2].name, "linear");
+        assert_eq!(stats[2].wins, 0);
+        assert_eq!(stats[2].worst, MAX_TOURNAMENT_ATTEMPTS);
+    }
+
+    #[test]
+    fn median_of_even_count() {
+        let stats = Stats::from_counts("test", vec![4, 1, 3, 2], 4);
+        assert_eq!(stats.median, 2.5);
+        assert_eq!(stats.mean, 2.5);
+        assert_eq!(stats.worst, 4);
+    }
+
+    #[test]
+    fn csv_has_one_row_per_strategy() {
+        let stats = vec![Stats::from_counts("binary", vec![7], 1)];
+        assert_eq!(
+            csv(&stats),
+            "strategy,games,wins,mean,median,worst\nbinary,1,1,7.0000,7,7\n"
+        );
+    }
+}
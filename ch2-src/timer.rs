@@ -0,0 +1,281 @@
+use crate::game::{self, Ending, Frontend, GuessingGame, Number};
+use std::time::{Duration, Instant};
+
+// Where the time comes from. The real game reads the system clock; the tests use a clock
+// they move forward by hand, so timeouts can be checked without actually waiting.
+pub trait Clock {
+    // The time since some fixed starting point.
+    fn now(&self) -> Duration;
+}
+
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl SystemClock {
+    pub fn new() -> SystemClock {
+        SystemClock {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        SystemClock::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TimeLimits {
+    // How long the player has for each guess. Running out costs an attempt.
+    pub per_guess: Option<Duration>,
+    // How long the whole game may take. Running out ends the game.
+    pub total: Option<Duration>,
+}
+
+// What a front end got when asked for a guess within a time limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reply {
+    Guess(Number),
+    TimedOut,
+    Closed,
+}
+
+// A front end that can stop waiting for input. It only has to give up eventually;
+// `play_timed` checks the clock itself, so a guess that arrives late still counts as late.
+pub trait TimedFrontend: Frontend {
+    // Waits at most `limit` for the next guess, or for as long as it takes if there is no limit.
+    fn read_guess_within(&mut self, game: &GuessingGame, limit: Option<Duration>) -> Reply;
+    fn timed_out(&mut self);
+}
+
+// Like `game::play`, but against the clock. Returns how the game ended and how long it took.
+pub fn play_timed(
+    game: &mut GuessingGame,
+    frontend: &mut impl TimedFrontend,
+    clock: &impl Clock,
+    limits: TimeLimits,
+) -> (Ending, Duration) {
+    let started = clock.now();
+    frontend.start(game);
+
+    let ending = loop {
+        if game.is_won() {
+            break Ending::Won {
+                guesses: game.history().len(),
+            };
+        }
+        if game.is_lost() {
+            break Ending::Lost {
+                secret: game.secret(),
+            };
+        }
+
+        let total_left = limits
+            .total
+            .map(|total| total.saturating_sub(clock.now() - started));
+        if total_left == Some(Duration::ZERO) {
+            break Ending::OutOfTime {
+                secret: game.secret(),
+            };
+        }
+        let limit = match (limits.per_guess, total_left) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+
+        let asked = clock.now();
+        let reply = frontend.read_guess_within(game, limit);
+        let late = limit.is_some_and(|limit| clock.now() - asked > limit);
+
+        match reply {
+            Reply::Closed => {
+                break Ending::Abandoned {
+                    guesses: game.history().len(),
+                }
+            }
+            Reply::Guess(guess) if !late => game::turn(game, frontend, guess),
+            // Either the front end gave up waiting or the guess came in too late. If that
+            // was the overall clock running out, the next time round the loop ends the game.
+            Reply::Guess(_) | Reply::TimedOut => {
+                if limits
+                    .total
+                    .is_none_or(|total| clock.now() - started < total)
+                {
+                    game.time_out();
+                    frontend.timed_out();
+                }
+            }
+        }
+    };
+
+    (ending, clock.now() - started)
+}
+
+// 1000 points for a first-guess win, less 100 for every further attempt (timeouts included)
+// and 10 for every whole second. Games that were not won score nothing.
+pub fn score(ending: &Ending, attempts: usize, elapsed: Duration) -> u64 {
+    match ending {
+        Ending::Won { .. } => 1000u64
+            .saturating_sub(100 * attempts.saturating_sub(1) as u64)
+            .saturating_sub(10 * elapsed.as_secs()),
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Outcome;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    #[derive(Default, Clone)]
+    struct ManualClock {
+        now: Rc<Cell<Duration>>,
+    }
+
+    impl ManualClock {
+        fn advance(&self, by: Duration) {
+            self.now.set(self.now.get() + by);
+        }
+    }
+
+    impl Clock for ManualClock {
+        fn now(&self) -> Duration {
+            self.now.get()
+        }
+    }
+
+    // Each step says how long the "player" takes and what they answer.
+    struct Scripted {
+        clock: ManualClock,
+        steps: Vec<(u64, Reply)>,
+        reports: Vec<(Number, Outcome)>,
+        timeouts: usize,
+    }
+
+    impl Scripted {
+        fn new(clock: &ManualClock, steps: Vec<(u64, Reply)>) -> Scripted {
+            Scripted {
+                clock: clock.clone(),
+                steps,
+                reports: Vec::new(),
+                timeouts: 0,
+            }
+        }
+    }
+
+    impl Frontend for Scripted {
+        fn start(&mut self, _game: &GuessingGame) {}
+
+        fn read_guess(&mut self, game: &GuessingGame) -> Option<Number> {
+            match self.read_guess_within(game, None) {
+                Reply::Guess(guess) => Some(guess),
+                _ => None,
+            }
+        }
+
+        fn report(&mut self, guess: Number, outcome: Outcome) {
+            self.reports.push((guess, outcome));
+        }
+    }
+
+    impl TimedFrontend for Scripted {
+        fn read_guess_within(&mut self, _game: &GuessingGame, _limit: Option<Duration>) -> Reply {
+            if self.steps.is_empty() {
+                return Reply::Closed;
+            }
+            let (seconds, reply) = self.steps.remove(0);
+            self.clock.advance(Duration::from_secs(seconds));
+            reply
+        }
+
+        fn timed_out(&mut self) {
+            self.timeouts += 1;
+        }
+    }
+
+    fn secs(seconds: u64) -> Option<Duration> {
+        Some(Duration::from_secs(seconds))
+    }
+
+    #[test]
+    fn late_guesses_waste_an_attempt() {
+        let clock = ManualClock::default();
+        let mut game = GuessingGame::new(7, 1..=10);
+        let mut frontend = Scripted::new(
+            &clock,
+            vec![
+                (3, Reply::Guess(5)),
+                (11, Reply::Guess(7)),
+                (10, Reply::TimedOut),
+                (2, Reply::Guess(7)),
+            ],
+        );
+        let limits = TimeLimits {
+            per_guess: secs(10),
+            total: None,
+        };
+
+        let (ending, elapsed) = play_timed(&mut game, &mut frontend, &clock, limits);
+        assert_eq!(ending, Ending::Won { guesses: 2 });
+        assert_eq!(
+            frontend.reports,
+            vec![(5, Outcome::TooSmall), (7, Outcome::Win)]
+        );
+        assert_eq!(frontend.timeouts, 2);
+        assert_eq!(game.attempts_used(), 4);
+        assert_eq!(elapsed, Duration::from_secs(26));
+        assert_eq!(score(&ending, 4, elapsed), 1000 - 300 - 260);
+    }
+
+    #[test]
+    fn timeouts_count_against_the_attempt_limit() {
+        let clock = ManualClock::default();
+        let mut game = GuessingGame::new(7, 1..=10).with_max_attempts(Some(2));
+        let mut frontend = Scripted::new(&clock, vec![(5, Reply::TimedOut), (6, Reply::Guess(7))]);
+        let limits = TimeLimits {
+            per_guess: secs(5),
+            total: None,
+        };
+
+        let (ending, _) = play_timed(&mut game, &mut frontend, &clock, limits);
+        assert_eq!(ending, Ending::Lost { secret: 7 });
+        assert!(frontend.reports.is_empty());
+    }
+
+    #[test]
+    fn overall_clock_ends_the_game() {
+        let clock = ManualClock::default();
+        let mut game = GuessingGame::new(7, 1..=10);
+        let mut frontend =
+            Scripted::new(&clock, vec![(20, Reply::Guess(1)), (15, Reply::Guess(7))]);
+        let limits = TimeLimits {
+            per_guess: secs(20),
+            total: secs(30),
+        };
+
+        let (ending, elapsed) = play_timed(&mut game, &mut frontend, &clock, limits);
+        assert_eq!(ending, Ending::OutOfTime { secret: 7 });
+        assert_eq!(elapsed, Duration::from_secs(35));
+        // Running out of overall time is not also a wasted attempt.
+        assert_eq!(frontend.timeouts, 0);
+        assert_eq!(score(&ending, 2, elapsed), 0);
+    }
+
+    #[test]
+    fn score_never_goes_below_zero() {
+        let ending = Ending::Won { guesses: 50 };
+        assert_eq!(score(&ending, 50, Duration::ZERO), 0);
+        assert_eq!(score(&ending, 1, Duration::from_secs(1000)), 0);
+        assert_eq!(score(&ending, 1, Duration::from_millis(999)), 1000);
+    }
+}
@@ -0,0 +1,267 @@
+// The chapter's "generate the nth Fibonacci number" exercise, for any n: F(0) = 0, F(1) = 1
+// and F(n) = F(n - 1) + F(n - 2). The numbers quickly outgrow every integer type (F(94)
+// does not fit in a u64), so they are computed with the `BigUint` type from `bigint`.
+pub mod bigint;
+
+use bigint::BigUint;
+use std::time::Instant;
+
+// Fast doubling uses two identities to go from F(k) and F(k + 1) straight to F(2k) and
+// F(2k + 1):
+//     F(2k)     = F(k) * (2 * F(k + 1) - F(k))
+//     F(2k + 1) = F(k)^2 + F(k + 1)^2
+// Walking through the bits of n from the top, each bit doubles k and a set bit adds one,
+// so F(n) takes about log2(n) steps instead of n additions.
+pub fn fibonacci(n: u64) -> BigUint {
+    let mut a = BigUint::default(); // F(k)
+    let mut b = BigUint::from(1); // F(k + 1)
+    for bit in (0..u64::BITS - n.leading_zeros()).rev() {
+        let twice_b = &b + &b;
+        let even = &a * &(&twice_b - &a);
+        let odd = &(&a * &a) + &(&b * &b);
+        if n >> bit & 1 == 1 {
+            b = &even + &odd;
+            a = odd;
+        } else {
+            a = even;
+            b = odd;
+        }
+    }
+    a
+}
+
+// The whole sequence, starting from F(0).
+#[derive(Debug, Clone)]
+pub struct Sequence {
+    current: BigUint,
+    next: BigUint,
+}
+
+impl Sequence {
+    pub fn new() -> Sequence {
+        Sequence {
+            current: BigUint::default(),
+            next: BigUint::from(1),
+        }
+    }
+}
+
+impl Default for Sequence {
+    fn default() -> Sequence {
+        Sequence::new()
+    }
+}
+
+impl Iterator for Sequence {
+    type Item = BigUint;
+
+    fn next(&mut self) -> Option<BigUint> {
+        let after = &self.current + &self.next;
+        let next = std::mem::replace(&mut self.next, after);
+        Some(std::mem::replace(&mut self.current, next))
+    }
+}
+
+// F(n) mod m, by the same doubling, with every step reduced mod m so nothing grows. With
+// m up to u64::MAX every product of two residues stays below m^2, which fits in a u128,
+// so each square is reduced before the two are added.
+pub fn fibonacci_mod(n: u64, m: u64) -> u64 {
+    if m == 0 {
+        panic!("Fibonacci modulus must be at least 1.");
+    }
+    let m = m as u128;
+    let (mut a, mut b) = (0u128, 1 % m);
+    for bit in (0..u64::BITS - n.leading_zeros()).rev() {
+        let even = a * ((2 * b + m - a) % m) % m;
+        let odd = (a * a % m + b * b % m) % m;
+        if n >> bit & 1 == 1 {
+            (a, b) = (odd, (even + odd) % m);
+        } else {
+            (a, b) = (even, odd);
+        }
+    }
+    a as u64
+}
+
+// The largest modulus `pisano_period` accepts. Stepping through up to 6m pairs takes a
+// few seconds here; a u64-sized modulus would never finish.
+pub const MAX_PISANO_MODULUS: u64 = 100_000_000;
+
+// The Pisano period: the Fibonacci numbers mod m repeat, and this is the length of the
+// cycle. It never exceeds 6m, so finding it by stepping through the sequence is O(m).
+pub fn pisano_period(m: u64) -> u64 {
+    if m == 0 {
+        panic!("Fibonacci modulus must be at least 1.");
+    }
+    if m > MAX_PISANO_MODULUS {
+        panic!("Pisano periods are only computed for moduli up to {MAX_PISANO_MODULUS}, got {m}.");
+    }
+    if m == 1 {
+        return 1;
+    }
+    let (mut a, mut b) = (0, 1);
+    let mut period = 0;
+    loop {
+        (a, b) = (b, ((a as u128 + b as u128) % m as u128) as u64);
+        period += 1;
+        if (a, b) == (0, 1) {
+            return period;
+        }
+    }
+}
+
+// Shows the first and last few digits of a long number, with the digit count.
+fn summary(number: &BigUint) -> String {
+    let text = number.to_string();
+    if text.len() <= 50 {
+        return text;
+    }
+    format!(
+        "{}...{} ({} digits)",
+        &text[..20],
+        &text[text.len() - 20..],
+        text.len()
+    )
+}
+
+// The command line: `<n>` prints F(n), `<n> --summary` only its ends and length,
+// `<n> --mod <m>` prints F(n) mod m, `--pisano <m>` the Pisano period of m and
+// `--first <count>` the start of the sequence. How long the work took goes to stderr,
+// so the numbers themselves can be piped somewhere.
+pub fn run_args(args: &[String]) -> Result<(), String> {
+    let number = |name: &str, value: &str| -> Result<u64, String> {
+        value
+            .parse()
+            .map_err(|_| format!("{name} must be a non-negative integer, got `{value}`"))
+    };
+    let modulus = |value: &str| -> Result<u64, String> {
+        match number("the modulus", value)? {
+            0 => Err(String::from("the modulus must be at least 1")),
+            m => Ok(m),
+        }
+    };
+
+    let start = Instant::now();
+    match args {
+        [flag, m] if flag == "--pisano" => {
+            let m = modulus(m)?;
+            if m > MAX_PISANO_MODULUS {
+                return Err(format!(
+                    "the Pisano modulus must be at most {MAX_PISANO_MODULUS}, got {m}"
+                ));
+            }
+            println!("pi({m}) = {}", pisano_period(m));
+        }
+        [flag, count] if flag == "--first" => {
+            let count = number("the count", count)?;
+            for (n, value) in Sequence::new().take(count as usize).enumerate() {
+                println!("F({n}) = {value}");
+            }
+        }
+        [n, flag, m] if flag == "--mod" => {
+            let (n, m) = (number("n", n)?, modulus(m)?);
+            println!("F({n}) mod {m} = {}", fibonacci_mod(n, m));
+        }
+        [n, rest @ ..] if rest.is_empty() || rest == ["--summary"] => {
+            let n = number("n", n)?;
+            let value = fibonacci(n);
+            let computed = start.elapsed();
+            if rest.is_empty() {
+                println!("{value}");
+            } else {
+                println!("F({n}) = {}", summary(&value));
+            }
+            eprintln!(
+                "computed F({n}) in {computed:.3?}, printed {} digits in {:.3?}",
+                value.digits(),
+                start.elapsed() - computed
+            );
+            return Ok(());
+        }
+        _ => {
+            return Err(String::from(
+                "expected <n>, <n> --summary, <n> --mod <m>, --pisano <m> or --first <count>",
+            ))
+        }
+    }
+    eprintln!("took {:.3?}", start.elapsed());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_values_match_the_sequence() {
+        let from_sequence: Vec<BigUint> = Sequence::new().take(200).collect();
+        for (n, expected) in from_sequence.iter().enumerate() {
+            assert_eq!(&fibonacci(n as u64), expected, "F({n})");
+        }
+        assert_eq!(fibonacci(10).to_string(), "55");
+        assert_eq!(fibonacci(100).to_string(), "354224848179261915075");
+    }
+
+    #[test]
+    fn large_values() {
+        let f = fibonacci(10_000);
+        assert_eq!(f.digits(), 2090);
+        assert!(f.to_string().starts_with("33644764876431783266"));
+        assert!(f.to_string().ends_with("66073310059947366875"));
+        // F(2n) = F(n) * (F(n - 1) + F(n + 1)) checks the big multiplication independently.
+        let (below, at, above) = (fibonacci(4_999), fibonacci(5_000), fibonacci(5_001));
+        assert_eq!(&at * &(&below + &above), f);
+    }
+
+    #[test]
+    fn modular_values_agree_with_big_ones() {
+        for m in [1, 2, 10, 97, 1_000_000_007] {
+            // F(186) is the last one that fits in a u128.
+            for n in [0, 1, 2, 50, 186] {
+                let big: u128 = fibonacci(n).to_string().parse().unwrap();
+                assert_eq!(fibonacci_mod(n, m) as u128, big % m as u128);
+            }
+        }
+        assert_eq!(fibonacci_mod(u64::MAX, 1), 0);
+        // The largest moduli still fit every intermediate product in a u128.
+        let m = u64::MAX;
+        assert_eq!(
+            fibonacci_mod(186, m) as u128,
+            fibonacci(186).to_string().parse::<u128>().unwrap() % m as u128
+        );
+        let (f0, f1, f2) = (
+            fibonacci_mod(998, m),
+            fibonacci_mod(999, m),
+            fibonacci_mod(1000, m),
+        );
+        assert_eq!(f2 as u128, (f0 as u128 + f1 as u128) % m as u128);
+        assert_eq!(
+            fibonacci_mod(10u64.pow(18), 10),
+            fibonacci_mod(10u64.pow(18) % 60, 10)
+        );
+    }
+
+    #[test]
+    fn pisano_periods() {
+        let known = [(1, 1), (2, 3), (3, 8), (5, 20), (10, 60), (1000, 1500)];
+        for (m, period) in known {
+            assert_eq!(pisano_period(m), period, "pi({m})");
+        }
+        // F(n) mod m only depends on n mod pi(m).
+        assert_eq!(
+            fibonacci_mod(1_000_123, 10),
+            fibonacci_mod(1_000_123 % 60, 10)
+        );
+    }
+
+    #[test]
+    fn command_line_errors() {
+        let args = |line: &str| -> Vec<String> { line.split(' ').map(String::from).collect() };
+        assert!(run_args(&[]).is_err());
+        assert!(run_args(&args("ten")).is_err());
+        assert!(run_args(&args("10 --mod 0")).is_err());
+        assert!(run_args(&args("10 --bogus")).is_err());
+        assert!(run_args(&args("--pisano 18446744073709551615")).is_err());
+        assert_eq!(summary(&BigUint::from(55)), "55");
+    }
+}
@@ -0,0 +1,274 @@
+// A non-negative integer of any size, just big enough for Fibonacci numbers: it can add,
+// subtract and multiply. The digits are stored in base 1_000_000_000, lowest first, so
+// printing even a million-digit number needs no division.
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Mul, Sub};
+use std::str::FromStr;
+
+const BASE: u64 = 1_000_000_000;
+const BASE_DIGITS: usize = 9;
+
+// Below this many limbs, schoolbook multiplication beats splitting the numbers up.
+const KARATSUBA_THRESHOLD: usize = 32;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BigUint {
+    // Never ends with a zero limb, so zero is the empty vector.
+    limbs: Vec<u32>,
+}
+
+fn trim(mut limbs: Vec<u32>) -> Vec<u32> {
+    while limbs.last() == Some(&0) {
+        limbs.pop();
+    }
+    limbs
+}
+
+// Adds `b` into `out`, starting `shift` limbs up.
+fn add_into(out: &mut Vec<u32>, b: &[u32], shift: usize) {
+    if out.len() < shift + b.len() {
+        out.resize(shift + b.len(), 0);
+    }
+    let mut carry = 0;
+    for (slot, &limb) in out[shift..].iter_mut().zip(b) {
+        let sum = *slot as u64 + limb as u64 + carry;
+        *slot = (sum % BASE) as u32;
+        carry = sum / BASE;
+    }
+    let mut i = shift + b.len();
+    while carry != 0 {
+        if i == out.len() {
+            out.push(0);
+        }
+        let sum = out[i] as u64 + carry;
+        out[i] = (sum % BASE) as u32;
+        carry = sum / BASE;
+        i += 1;
+    }
+}
+
+// Subtracts `b` from `out`, which must be at least as large.
+fn sub_from(out: &mut [u32], b: &[u32]) {
+    let mut borrow = 0;
+    for (i, slot) in out.iter_mut().enumerate() {
+        let take = b.get(i).copied().unwrap_or(0) as i64 + borrow;
+        if take == 0 && i >= b.len() {
+            break;
+        }
+        let mut limb = *slot as i64 - take;
+        borrow = 0;
+        if limb < 0 {
+            limb += BASE as i64;
+            borrow = 1;
+        }
+        *slot = limb as u32;
+    }
+    if borrow != 0 {
+        panic!("attempt to subtract a larger BigUint from a smaller one");
+    }
+}
+
+fn schoolbook(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut out = vec![0u32; a.len() + b.len()];
+    for (i, &x) in a.iter().enumerate() {
+        let mut carry = 0;
+        for (j, &y) in b.iter().enumerate() {
+            let cur = out[i + j] as u64 + x as u64 * y as u64 + carry;
+            out[i + j] = (cur % BASE) as u32;
+            carry = cur / BASE;
+        }
+        out[i + b.len()] = carry as u32;
+    }
+    out
+}
+
+// Karatsuba multiplication: split both numbers as `high * BASE^half + low`, then three
+// half-size products are enough instead of four, which makes it about O(n^1.58).
+fn multiply(a: &[u32], b: &[u32]) -> Vec<u32> {
+    if a.len().min(b.len()) < KARATSUBA_THRESHOLD {
+        return trim(schoolbook(a, b));
+    }
+    let half = a.len().max(b.len()) / 2;
+    let (a_low, a_high) = a.split_at(half.min(a.len()));
+    let (b_low, b_high) = b.split_at(half.min(b.len()));
+    let a_low = &a_low[..a_low.len() - a_low.iter().rev().take_while(|&&l| l == 0).count()];
+    let b_low = &b_low[..b_low.len() - b_low.iter().rev().take_while(|&&l| l == 0).count()];
+
+    let low = multiply(a_low, b_low);
+    let high = multiply(a_high, b_high);
+    let mut a_sum = a_low.to_vec();
+    add_into(&mut a_sum, a_high, 0);
+    let mut b_sum = b_low.to_vec();
+    add_into(&mut b_sum, b_high, 0);
+    let mut middle = multiply(&a_sum, &b_sum);
+    sub_from(&mut middle, &low);
+    sub_from(&mut middle, &high);
+
+    let mut out = low;
+    add_into(&mut out, &trim(middle), half);
+    add_into(&mut out, &high, 2 * half);
+    trim(out)
+}
+
+impl BigUint {
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    // The number of decimal digits, counting zero as one digit.
+    pub fn digits(&self) -> usize {
+        match self.limbs.last() {
+            None => 1,
+            Some(top) => (self.limbs.len() - 1) * BASE_DIGITS + top.to_string().len(),
+        }
+    }
+}
+
+impl From<u64> for BigUint {
+    fn from(mut n: u64) -> BigUint {
+        let mut limbs = Vec::new();
+        while n != 0 {
+            limbs.push((n % BASE) as u32);
+            n /= BASE;
+        }
+        BigUint { limbs }
+    }
+}
+
+impl Add<&BigUint> for &BigUint {
+    type Output = BigUint;
+
+    fn add(self, other: &BigUint) -> BigUint {
+        let mut limbs = self.limbs.clone();
+        add_into(&mut limbs, &other.limbs, 0);
+        BigUint { limbs }
+    }
+}
+
+impl Sub<&BigUint> for &BigUint {
+    type Output = BigUint;
+
+    // Panics if `other` is larger, like subtraction on the unsigned integer types.
+    fn sub(self, other: &BigUint) -> BigUint {
+        if other.limbs.len() > self.limbs.len() {
+            panic!("attempt to subtract a larger BigUint from a smaller one");
+        }
+        let mut limbs = self.limbs.clone();
+        sub_from(&mut limbs, &other.limbs);
+        BigUint { limbs: trim(limbs) }
+    }
+}
+
+impl Mul<&BigUint> for &BigUint {
+    type Output = BigUint;
+
+    fn mul(self, other: &BigUint) -> BigUint {
+        BigUint {
+            limbs: multiply(&self.limbs, &other.limbs),
+        }
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &BigUint) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &BigUint) -> Ordering {
+        self.limbs
+            .len()
+            .cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+impl fmt::Display for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Some((top, rest)) = self.limbs.split_last() else {
+            return write!(f, "0");
+        };
+        let mut text = String::with_capacity(self.digits());
+        text.push_str(&top.to_string());
+        for limb in rest.iter().rev() {
+            text.push_str(&format!("{limb:09}"));
+        }
+        f.pad_integral(true, "", &text)
+    }
+}
+
+impl FromStr for BigUint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<BigUint, String> {
+        if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(format!("`{s}` is not a non-negative integer"));
+        }
+        let limbs = s
+            .as_bytes()
+            .rchunks(BASE_DIGITS)
+            .map(|chunk| std::str::from_utf8(chunk).unwrap().parse().unwrap())
+            .collect();
+        Ok(BigUint { limbs: trim(limbs) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big(s: &str) -> BigUint {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn arithmetic_carries_across_limbs() {
+        let a = big("999999999999999999");
+        assert_eq!((&a + &BigUint::from(1)).to_string(), "1000000000000000000");
+        assert_eq!((&big("1000000000000000000") - &BigUint::from(1)), a);
+        assert_eq!(
+            (&a * &a).to_string(),
+            "999999999999999998000000000000000001"
+        );
+        assert!((&a - &a).is_zero());
+        assert_eq!(big("000").to_string(), "0");
+    }
+
+    #[test]
+    #[should_panic(expected = "subtract")]
+    fn subtraction_cannot_go_negative() {
+        let _ = &BigUint::from(1) - &BigUint::from(2);
+    }
+
+    #[test]
+    fn karatsuba_matches_schoolbook() {
+        // 10^(9*80) - 1 squared, big enough to go through several levels of splitting.
+        let nines = big(&"9".repeat(9 * 80));
+        let square = &nines * &nines;
+        let expected = format!("{}8{}1", "9".repeat(9 * 80 - 1), "0".repeat(9 * 80 - 1));
+        assert_eq!(square.to_string(), expected);
+        assert_eq!(square.limbs, trim(schoolbook(&nines.limbs, &nines.limbs)));
+
+        // Numbers of very different sizes, and ones with zero limbs in the middle.
+        let sparse = big(&format!("1{}1", "0".repeat(500)));
+        assert_eq!(
+            (&sparse * &nines).limbs,
+            trim(schoolbook(&sparse.limbs, &nines.limbs))
+        );
+        assert_eq!(
+            (&sparse * &BigUint::from(7)).to_string(),
+            format!("7{}7", "0".repeat(500))
+        );
+    }
+
+    #[test]
+    fn ordering_and_digits() {
+        assert!(big("1000000000") > big("999999999"));
+        assert!(big("123456789123") < big("123456789124"));
+        assert_eq!(BigUint::default().digits(), 1);
+        assert_eq!(big("1000000000").digits(), 10);
+        assert_eq!(format!("{:>5}", BigUint::from(42)), "   42");
+    }
+}
@@ -0,0 +1,143 @@
+// A small interpreter for the part of Rust this chapter covers: `let`, blocks as expressions,
+// `if` as an expression, `loop` with `break value`, `while`, `for` over ranges and functions.
+// Source code goes through three steps: the lexer splits it into tokens, the parser turns the
+// tokens into a tree of expressions and statements, and the evaluator works out the value.
+pub mod eval;
+pub mod lexer;
+pub mod parser;
+
+use eval::{Interpreter, Value};
+use std::fmt;
+use std::io::{self, BufRead, Write};
+
+// A position in the source code, counted from 1 like compiler error messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pos {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Pos {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+// Lexing, parsing and evaluating all report problems the same way.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    pub pos: Pos,
+    pub message: String,
+    // Extra help, such as which semicolon turned a value into `()`.
+    pub note: Option<String>,
+}
+
+impl Error {
+    pub fn new(pos: Pos, message: impl Into<String>) -> Error {
+        Error {
+            pos,
+            message: message.into(),
+            note: None,
+        }
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Error {
+        self.note = Some(note.into());
+        self
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "error at {}: {}", self.pos, self.message)?;
+        if let Some(note) = &self.note {
+            write!(f, "\nnote: {note}")?;
+        }
+        Ok(())
+    }
+}
+
+// Runs one piece of source code with `interpreter`, keeping whatever it defines.
+pub fn run(interpreter: &mut Interpreter, source: &str) -> Result<Value, Error> {
+    let tokens = lexer::lex(source)?;
+    let program = parser::parse(tokens)?;
+    interpreter.run(&program)
+}
+
+// Input is complete once every `{` and `(` has been closed, so a block or function
+// can be typed over several lines.
+fn is_complete(source: &str) -> bool {
+    let Ok(tokens) = lexer::lex(source) else {
+        // Let `run` report the error.
+        return true;
+    };
+    let depth: i64 = tokens
+        .iter()
+        .map(|spanned| match spanned.token {
+            lexer::Token::LBrace | lexer::Token::LParen => 1,
+            lexer::Token::RBrace | lexer::Token::RParen => -1,
+            _ => 0,
+        })
+        .sum();
+    depth <= 0
+}
+
+// Reads input from stdin, runs it and prints the value, until the end of the input.
+pub fn repl() {
+    let mut interpreter = Interpreter::new();
+    let mut input = String::new();
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+
+    println!("Type expressions or statements; end the input with Ctrl-D.");
+    loop {
+        print!("{}", if input.is_empty() { ">> " } else { ".. " });
+        io::stdout().flush().expect("Failed to flush stdout.");
+
+        let Some(Ok(line)) = lines.next() else {
+            println!();
+            return;
+        };
+        input.push_str(&line);
+        input.push('\n');
+        if !is_complete(&input) {
+            continue;
+        }
+
+        match run(&mut interpreter, &input) {
+            Ok(value) => {
+                println!("{value}");
+                if let Some(semi) = value.stray_semicolon() {
+                    println!("note: {}", eval::stray_note(semi));
+                }
+            }
+            Err(error) => println!("{error}"),
+        }
+        input.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repl_keeps_definitions() {
+        let mut interpreter = Interpreter::new();
+        run(&mut interpreter, "fn five() -> i32 { 5 } let x = five();").unwrap();
+        assert_eq!(run(&mut interpreter, "x + 1"), Ok(Value::Int(6)));
+        assert_eq!(run(&mut interpreter, "five() * 2"), Ok(Value::Int(10)));
+    }
+
+    #[test]
+    fn multi_line_input() {
+        assert!(!is_complete("fn five() -> i32 {"));
+        assert!(is_complete("fn five() -> i32 {\n5\n}"));
+    }
+
+    #[test]
+    fn errors_show_position_and_note() {
+        let error = Error::new(Pos { line: 2, column: 5 }, "oops").with_note("try again");
+        assert_eq!(error.to_string(), "error at 2:5: oops\nnote: try again");
+    }
+}
@@ -0,0 +1,650 @@
+use super::parser::{BinOp, Block, Expr, Function, Stmt, Type, UnaryOp};
+use super::{Error, Pos};
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Value {
+    Int(i64),
+    Bool(bool),
+    // The unit value `()`. When it came from a block whose last expression was cut off by a
+    // semicolon, the position of that semicolon is kept so errors can point at it.
+    Unit(Option<Pos>),
+}
+
+impl Value {
+    fn ty(&self) -> Type {
+        match self {
+            Value::Int(_) => Type::Int,
+            Value::Bool(_) => Type::Bool,
+            Value::Unit(_) => Type::Unit,
+        }
+    }
+
+    // The stray semicolon behind a `()`, if there was one.
+    pub fn stray_semicolon(&self) -> Option<Pos> {
+        match self {
+            Value::Unit(stray) => *stray,
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Int(value) => write!(f, "{value}"),
+            Value::Bool(value) => write!(f, "{value}"),
+            Value::Unit(_) => write!(f, "()"),
+        }
+    }
+}
+
+fn type_name(ty: Type) -> &'static str {
+    match ty {
+        Type::Int => "integer",
+        Type::Bool => "`bool`",
+        Type::Unit => "`()`",
+    }
+}
+
+// The note that explains where an unexpected `()` came from.
+pub fn stray_note(semi: Pos) -> String {
+    format!("the `;` at {semi} turned the block's last expression into a statement, so the block's value is `()`; remove it to keep the value")
+}
+
+fn mismatch(pos: Pos, expected: Type, found: Value) -> Error {
+    let error = Error::new(
+        pos,
+        format!(
+            "expected {}, found {}",
+            type_name(expected),
+            type_name(found.ty())
+        ),
+    );
+    match found.stray_semicolon() {
+        Some(semi) => error.with_note(stray_note(semi)),
+        None => error,
+    }
+}
+
+// Besides errors, evaluation can be cut short by `break`, `continue` and `return`,
+// which travel up to the loop or function that handles them.
+enum Unwind {
+    Error(Error),
+    Break(Value, Pos),
+    Continue(Pos),
+    Return(Value),
+}
+
+impl From<Error> for Unwind {
+    fn from(error: Error) -> Unwind {
+        Unwind::Error(error)
+    }
+}
+
+type Eval<T> = Result<T, Unwind>;
+
+#[derive(Default)]
+struct Scope {
+    vars: HashMap<String, (Value, bool)>,
+    fns: HashMap<String, Rc<Function>>,
+}
+
+// Holds the variables and functions defined so far, so a REPL can keep one
+// interpreter and build on earlier input.
+pub struct Interpreter {
+    // Each function call gets a frame of its own: like Rust functions, it cannot
+    // see the caller's variables, only the functions defined at the top level.
+    frames: Vec<Vec<Scope>>,
+    steps: u64,
+    // How many `expr` calls are under way, however they nest: calls, blocks, brackets.
+    depth: usize,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Interpreter::new()
+    }
+}
+
+impl Interpreter {
+    // Guards against input that never finishes, which would otherwise hang the REPL.
+    pub const MAX_STEPS: u64 = 10_000_000;
+    // How deeply expressions may nest while running, counting every call the program
+    // makes together with the brackets and blocks inside each one. Each level is a few
+    // nested calls in the evaluator, so deep recursion is stopped well before it could
+    // overflow the real stack, even in a debug build.
+    pub const MAX_DEPTH: usize = 100;
+
+    pub fn new() -> Interpreter {
+        Interpreter {
+            frames: vec![vec![Scope::default()]],
+            steps: 0,
+            depth: 0,
+        }
+    }
+
+    // Runs a program at the top level. Variables and functions it defines stay around.
+    pub fn run(&mut self, program: &Block) -> Result<Value, Error> {
+        self.steps = 0;
+        match self.block_body(program) {
+            Ok(value) => Ok(value),
+            Err(Unwind::Error(error)) => Err(error),
+            Err(Unwind::Break(_, pos)) | Err(Unwind::Continue(pos)) => Err(Error::new(
+                pos,
+                "`break` and `continue` only work inside a loop",
+            )),
+            Err(Unwind::Return(_)) => Err(Error::new(
+                program_start(),
+                "`return` only works inside a function",
+            )),
+        }
+    }
+
+    fn scopes(&mut self) -> &mut Vec<Scope> {
+        self.frames.last_mut().unwrap()
+    }
+
+    fn lookup_var(&self, name: &str) -> Option<&(Value, bool)> {
+        self.frames
+            .last()
+            .unwrap()
+            .iter()
+            .rev()
+            .find_map(|scope| scope.vars.get(name))
+    }
+
+    fn lookup_fn(&self, name: &str) -> Option<Rc<Function>> {
+        let current = self.frames.last().unwrap().iter().rev();
+        let globals = self.frames[0].iter().take(1);
+        current
+            .chain(globals)
+            .find_map(|scope| scope.fns.get(name).cloned())
+    }
+
+    fn step(&mut self, pos: Pos) -> Eval<()> {
+        self.steps += 1;
+        if self.steps > Interpreter::MAX_STEPS {
+            return Err(Error::new(
+                pos,
+                "gave up after too many steps; is there an endless loop?",
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    fn block(&mut self, block: &Block) -> Eval<Value> {
+        self.scopes().push(Scope::default());
+        let result = self.block_body(block);
+        self.scopes().pop();
+        result
+    }
+
+    fn block_body(&mut self, block: &Block) -> Eval<Value> {
+        // Functions can be called before the line that defines them, as in Rust.
+        for stmt in &block.stmts {
+            if let Stmt::Fn(function) = stmt {
+                let scope = self.scopes().last_mut().unwrap();
+                scope.fns.insert(function.name.clone(), Rc::clone(function));
+            }
+        }
+
+        let mut last = Value::Unit(None);
+        for stmt in &block.stmts {
+            last = Value::Unit(None);
+            match stmt {
+                Stmt::Let {
+                    name,
+                    mutable,
+                    ty,
+                    value,
+                    pos,
+                } => {
+                    let value = self.expr(value)?;
+                    if let Some(ty) = ty {
+                        if value.ty() != *ty {
+                            return Err(mismatch(*pos, *ty, value).into());
+                        }
+                    }
+                    let scope = self.scopes().last_mut().unwrap();
+                    scope.vars.insert(name.clone(), (value, *mutable));
+                }
+                Stmt::Expr { expr, semi } => {
+                    let value = self.expr(expr)?;
+                    // Remember a value that the semicolon threw away, in case
+                    // this turns out to be the last statement of the block.
+                    if let (Some(semi), false) = (semi, value.ty() == Type::Unit) {
+                        last = Value::Unit(Some(*semi));
+                    }
+                }
+                Stmt::Fn(_) => {}
+            }
+        }
+
+        match &block.tail {
+            Some(tail) => self.expr(tail),
+            None => Ok(last),
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) -> Eval<Value> {
+        self.depth += 1;
+        let result = self.nested_expr(expr);
+        self.depth -= 1;
+        result
+    }
+
+    fn nested_expr(&mut self, expr: &Expr) -> Eval<Value> {
+        match expr {
+            Expr::Int(value) => Ok(Value::Int(*value)),
+            Expr::Bool(value) => Ok(Value::Bool(*value)),
+            Expr::Unit => Ok(Value::Unit(None)),
+            Expr::Var(name, pos) => match self.lookup_var(name) {
+                Some((value, _)) => Ok(*value),
+                None => Err(
+                    Error::new(*pos, format!("cannot find value `{name}` in this scope")).into(),
+                ),
+            },
+            Expr::Unary(op, operand, pos) => {
+                let value = self.expr(operand)?;
+                match (op, value) {
+                    (UnaryOp::Neg, Value::Int(n)) => n
+                        .checked_neg()
+                        .map(Value::Int)
+                        .ok_or_else(|| Error::new(*pos, "attempt to negate with overflow").into()),
+                    (UnaryOp::Not, Value::Bool(b)) => Ok(Value::Bool(!b)),
+                    (UnaryOp::Neg, other) => Err(mismatch(*pos, Type::Int, other).into()),
+                    (UnaryOp::Not, other) => Err(mismatch(*pos, Type::Bool, other).into()),
+                }
+            }
+            Expr::Binary(op, left, right, pos) => {
+                let left = self.expr(left)?;
+                // `&&` and `||` only look at the right-hand side when they need to.
+                match (op, left) {
+                    (BinOp::And, Value::Bool(false)) => return Ok(Value::Bool(false)),
+                    (BinOp::Or, Value::Bool(true)) => return Ok(Value::Bool(true)),
+                    _ => {}
+                }
+                let right = self.expr(right)?;
+                Ok(binary(*op, left, right, *pos)?)
+            }
+            Expr::Assign {
+                name,
+                op,
+                value,
+                pos,
+            } => {
+                let value = self.expr(value)?;
+                let Some(&(current, mutable)) = self.lookup_var(name) else {
+                    return Err(Error::new(
+                        *pos,
+                        format!("cannot find value `{name}` in this scope"),
+                    )
+                    .into());
+                };
+                if !mutable {
+                    return Err(Error::new(
+                        *pos,
+                        format!("cannot assign twice to immutable variable `{name}`"),
+                    )
+                    .with_note(format!("make it mutable with `let mut {name}`"))
+                    .into());
+                }
+                let value = match op {
+                    Some(op) => binary(*op, current, value, *pos)?,
+                    None if value.ty() != current.ty() => {
+                        return Err(mismatch(*pos, current.ty(), value).into())
+                    }
+                    None => value,
+                };
+                for scope in self.scopes().iter_mut().rev() {
+                    if let Some(slot) = scope.vars.get_mut(name) {
+                        slot.0 = value;
+                        break;
+                    }
+                }
+                Ok(Value::Unit(None))
+            }
+            Expr::Block(block) => self.block(block),
+            Expr::If {
+                condition,
+                then,
+                otherwise,
+                pos,
+            } => {
+                let condition = self.expr(condition)?;
+                match condition {
+                    Value::Bool(true) => self.block(then),
+                    Value::Bool(false) => match otherwise {
+                        Some(otherwise) => self.expr(otherwise),
+                        None => Ok(Value::Unit(None)),
+                    },
+                    other => Err(mismatch(*pos, Type::Bool, other).into()),
+                }
+            }
+            Expr::Loop(body, pos) => loop {
+                self.step(*pos)?;
+                match self.block(body) {
+                    Ok(_) | Err(Unwind::Continue(_)) => {}
+                    Err(Unwind::Break(value, _)) => return Ok(value),
+                    Err(other) => return Err(other),
+                }
+            },
+            Expr::While {
+                condition,
+                body,
+                pos,
+            } => loop {
+                self.step(*pos)?;
+                match self.expr(condition)? {
+                    Value::Bool(true) => {}
+                    Value::Bool(false) => return Ok(Value::Unit(None)),
+                    other => return Err(mismatch(*pos, Type::Bool, other).into()),
+                }
+                match self.block(body) {
+                    Ok(_) | Err(Unwind::Continue(_)) => {}
+                    Err(Unwind::Break(Value::Unit(_), _)) => return Ok(Value::Unit(None)),
+                    Err(Unwind::Break(_, pos)) => {
+                        return Err(Error::new(
+                            pos,
+                            "`break` with a value only works inside `loop`",
+                        )
+                        .into())
+                    }
+                    Err(other) => return Err(other),
+                }
+            },
+            Expr::For {
+                var,
+                start,
+                end,
+                inclusive,
+                reversed,
+                body,
+                pos,
+            } => {
+                let start = match self.expr(start)? {
+                    Value::Int(n) => n,
+                    other => return Err(mismatch(*pos, Type::Int, other).into()),
+                };
+                let end = match self.expr(end)? {
+                    Value::Int(n) => n,
+                    other => return Err(mismatch(*pos, Type::Int, other).into()),
+                };
+                let numbers: Box<dyn Iterator<Item = i64>> = match (inclusive, reversed) {
+                    (false, false) => Box::new(start..end),
+                    (true, false) => Box::new(start..=end),
+                    (false, true) => Box::new((start..end).rev()),
+                    (true, true) => Box::new((start..=end).rev()),
+                };
+                for n in numbers {
+                    self.step(*pos)?;
+                    self.scopes().push(Scope::default());
+                    let scope = self.scopes().last_mut().unwrap();
+                    scope.vars.insert(var.clone(), (Value::Int(n), false));
+                    let result = self.block(body);
+                    self.scopes().pop();
+                    match result {
+                        Ok(_) | Err(Unwind::Continue(_)) => {}
+                        Err(Unwind::Break(Value::Unit(_), _)) => break,
+                        Err(Unwind::Break(_, pos)) => {
+                            return Err(Error::new(
+                                pos,
+                                "`break` with a value only works inside `loop`",
+                            )
+                            .into())
+                        }
+                        Err(other) => return Err(other),
+                    }
+                }
+                Ok(Value::Unit(None))
+            }
+            Expr::Break(value, pos) => {
+                self.step(*pos)?;
+                let value = match value {
+                    Some(value) => self.expr(value)?,
+                    None => Value::Unit(None),
+                };
+                Err(Unwind::Break(value, *pos))
+            }
+            Expr::Continue(pos) => {
+                self.step(*pos)?;
+                Err(Unwind::Continue(*pos))
+            }
+            Expr::Return(value, _) => {
+                let value = match value {
+                    Some(value) => self.expr(value)?,
+                    None => Value::Unit(None),
+                };
+                Err(Unwind::Return(value))
+            }
+            Expr::Call(name, args, pos) => self.call(name, args, *pos),
+        }
+    }
+
+    fn call(&mut self, name: &str, args: &[Expr], pos: Pos) -> Eval<Value> {
+        self.step(pos)?;
+        let Some(function) = self.lookup_fn(name) else {
+            return Err(
+                Error::new(pos, format!("cannot find function `{name}` in this scope")).into(),
+            );
+        };
+        if args.len() != function.params.len() {
+            return Err(Error::new(
+                pos,
+                format!(
+                    "`{name}` takes {} arguments but {} were supplied",
+                    function.params.len(),
+                    args.len()
+                ),
+            )
+            .into());
+        }
+        if self.depth > Interpreter::MAX_DEPTH {
+            return Err(Error::new(pos, format!("too much recursion calling `{name}`")).into());
+        }
+
+        let mut scope = Scope::default();
+        for (arg, (param, ty)) in args.iter().zip(&function.params) {
+            let value = self.expr(arg)?;
+            if value.ty() != *ty {
+                return Err(mismatch(pos, *ty, value).into());
+            }
+            scope.vars.insert(param.clone(), (value, false));
+        }
+
+        self.frames.push(vec![scope]);
+        let result = self.block(&function.body);
+        self.frames.pop();
+
+        let value = match result {
+            Ok(value) | Err(Unwind::Return(value)) => value,
+            Err(Unwind::Break(_, pos)) | Err(Unwind::Continue(pos)) => {
+                return Err(
+                    Error::new(pos, "`break` and `continue` only work inside a loop").into(),
+                )
+            }
+            Err(error) => return Err(error),
+        };
+        if value.ty() != function.returns {
+            let error = Error::new(
+                function.pos,
+                format!(
+                    "function `{name}` should return {} but its body has the value {}",
+                    type_name(function.returns),
+                    type_name(value.ty())
+                ),
+            );
+            let error = match value.stray_semicolon() {
+                Some(semi) => error.with_note(stray_note(semi)),
+                None => error,
+            };
+            return Err(error.into());
+        }
+        Ok(value)
+    }
+}
+
+fn program_start() -> Pos {
+    Pos { line: 1, column: 1 }
+}
+
+// Arithmetic checks for overflow the way a debug build of Rust does, but reports an error
+// instead of panicking.
+fn binary(op: BinOp, left: Value, right: Value, pos: Pos) -> Result<Value, Error> {
+    use Value::{Bool, Int};
+
+    let overflow = |what: &str| Error::new(pos, format!("attempt to {what} with overflow"));
+    match (op, left, right) {
+        (BinOp::Add, Int(a), Int(b)) => a.checked_add(b).map(Int).ok_or_else(|| overflow("add")),
+        (BinOp::Sub, Int(a), Int(b)) => a
+            .checked_sub(b)
+            .map(Int)
+            .ok_or_else(|| overflow("subtract")),
+        (BinOp::Mul, Int(a), Int(b)) => a
+            .checked_mul(b)
+            .map(Int)
+            .ok_or_else(|| overflow("multiply")),
+        (BinOp::Div | BinOp::Rem, Int(_), Int(0)) => {
+            Err(Error::new(pos, "attempt to divide by zero"))
+        }
+        (BinOp::Div, Int(a), Int(b)) => a.checked_div(b).map(Int).ok_or_else(|| overflow("divide")),
+        (BinOp::Rem, Int(a), Int(b)) => a
+            .checked_rem(b)
+            .map(Int)
+            .ok_or_else(|| overflow("calculate the remainder")),
+        (BinOp::Lt, Int(a), Int(b)) => Ok(Bool(a < b)),
+        (BinOp::Le, Int(a), Int(b)) => Ok(Bool(a <= b)),
+        (BinOp::Gt, Int(a), Int(b)) => Ok(Bool(a > b)),
+        (BinOp::Ge, Int(a), Int(b)) => Ok(Bool(a >= b)),
+        (BinOp::Eq | BinOp::Ne, a, b) if a.ty() == b.ty() => {
+            let equal = match (a, b) {
+                (Int(a), Int(b)) => a == b,
+                (Bool(a), Bool(b)) => a == b,
+                _ => true,
+            };
+            Ok(Bool(equal == (op == BinOp::Eq)))
+        }
+        (BinOp::And, Bool(a), Bool(b)) => Ok(Bool(a && b)),
+        (BinOp::Or, Bool(a), Bool(b)) => Ok(Bool(a || b)),
+        // Whatever is left is a type error. Blame the operand that has the wrong type.
+        (BinOp::And | BinOp::Or, Bool(_), other) | (BinOp::And | BinOp::Or, other, _) => {
+            Err(mismatch(pos, Type::Bool, other))
+        }
+        (BinOp::Eq | BinOp::Ne, a, b) => Err(mismatch(pos, a.ty(), b)),
+        (_, Int(_), other) | (_, other, _) => Err(mismatch(pos, Type::Int, other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::{lexer::lex, parser::parse};
+
+    fn run(source: &str) -> Result<Value, Error> {
+        Interpreter::new().run(&parse(lex(source)?)?)
+    }
+
+    #[test]
+    fn blocks_are_expressions() {
+        assert_eq!(run("let y = { let x = 3; x + 1 }; y"), Ok(Value::Int(4)));
+        assert_eq!(
+            run("let n = if true { 5 } else { 6 }; n"),
+            Ok(Value::Int(5))
+        );
+    }
+
+    #[test]
+    fn chapter_three_examples() {
+        let source = "
+            fn five() -> i32 {
+                5
+            }
+
+            let mut counter = 0;
+            let result = loop {
+                counter += 1;
+                if counter == 10 {
+                    break counter * 2;
+                }
+            };
+
+            let mut countdown = 0;
+            for number in (1..4).rev() {
+                countdown = countdown * 10 + number;
+            }
+            result + five() + countdown
+        ";
+        assert_eq!(run(source), Ok(Value::Int(20 + 5 + 321)));
+    }
+
+    #[test]
+    fn stray_semicolon_in_a_block() {
+        let error = run("let y = {\n    let x = 3;\n    x + 1;\n};\ny * 2").unwrap_err();
+        assert_eq!(error.message, "expected integer, found `()`");
+        assert!(error.note.unwrap().contains("`;` at 3:10"));
+    }
+
+    #[test]
+    fn stray_semicolon_in_a_function() {
+        let error = run("fn plus_one(x: i32) -> i32 {\n    x + 1;\n}\nplus_one(5)").unwrap_err();
+        assert_eq!(error.pos, Pos { line: 1, column: 1 });
+        assert!(error.message.contains("should return integer"));
+        assert!(error.note.unwrap().contains("`;` at 2:10"));
+    }
+
+    #[test]
+    fn functions_cannot_see_the_callers_variables() {
+        assert!(run("fn f() -> i32 { x } let x = 1; f()").is_err());
+        assert_eq!(
+            run("fn fact(n: i32) -> i32 { if n == 0 { 1 } else { n * fact(n - 1) } } fact(10)"),
+            Ok(Value::Int(3_628_800))
+        );
+    }
+
+    #[test]
+    fn runtime_errors() {
+        assert!(run("let x = 1; x = 2;")
+            .unwrap_err()
+            .message
+            .contains("immutable"));
+        assert!(run("1 / 0").is_err());
+        assert!(run("if 1 { 2 }").is_err());
+        assert!(run("9223372036854775807 + 1").is_err());
+        assert!(run("break 3").is_err());
+        assert!(run("loop {}").is_err());
+        assert!(run("fn f(n: i32) -> i32 { f(n) } f(1)").is_err());
+    }
+
+    #[test]
+    fn blocks_count_toward_the_recursion_limit() {
+        let source = format!(
+            "fn f(n: i32) -> i32 {{ if n == 0 {{ 0 }} else {{ {}f(n - 1){} }} }} f(1000)",
+            "{ ".repeat(45),
+            " }".repeat(45)
+        );
+        assert!(run(&source)
+            .unwrap_err()
+            .message
+            .contains("too much recursion"));
+    }
+
+    #[test]
+    fn return_and_continue() {
+        let source = "
+            fn first_even_above(n: i32) -> i32 {
+                let mut i = n;
+                loop {
+                    i += 1;
+                    if i % 2 == 1 { continue; }
+                    return i;
+                }
+            }
+            first_even_above(7)
+        ";
+        assert_eq!(run(source), Ok(Value::Int(8)));
+    }
+}
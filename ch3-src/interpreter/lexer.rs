@@ -0,0 +1,242 @@
+use super::{Error, Pos};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token {
+    Int(i64),
+    Ident(String),
+    // Keywords
+    Let,
+    Mut,
+    Fn,
+    If,
+    Else,
+    Loop,
+    While,
+    For,
+    In,
+    Break,
+    Continue,
+    Return,
+    True,
+    False,
+    // Punctuation
+    LParen,
+    RParen,
+    LBrace,
+    RBrace,
+    Comma,
+    Semi,
+    Colon,
+    Arrow,
+    Dot,
+    DotDot,
+    DotDotEq,
+    // Operators
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Percent,
+    Assign,
+    PlusAssign,
+    MinusAssign,
+    StarAssign,
+    SlashAssign,
+    EqEq,
+    NotEq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    AndAnd,
+    OrOr,
+    Bang,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Spanned {
+    pub token: Token,
+    pub pos: Pos,
+}
+
+// Splits source code into tokens, each with the line and column where it starts.
+pub fn lex(source: &str) -> Result<Vec<Spanned>, Error> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    let mut pos = Pos { line: 1, column: 1 };
+
+    while i < chars.len() {
+        let c = chars[i];
+        let start = pos;
+        let next = chars.get(i + 1).copied();
+
+        if c == '\n' {
+            i += 1;
+            pos = Pos {
+                line: pos.line + 1,
+                column: 1,
+            };
+            continue;
+        }
+        if c.is_whitespace() {
+            i += 1;
+            pos.column += 1;
+            continue;
+        }
+        // A `//` comment runs to the end of the line.
+        if c == '/' && next == Some('/') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            continue;
+        }
+
+        let (token, len) = if c.is_ascii_digit() {
+            let len = chars[i..]
+                .iter()
+                .take_while(|c| c.is_ascii_digit() || **c == '_')
+                .count();
+            let digits: String = chars[i..i + len].iter().filter(|c| **c != '_').collect();
+            let value = digits.parse().map_err(|_| {
+                Error::new(start, format!("integer literal `{digits}` is too large"))
+            })?;
+            (Token::Int(value), len)
+        } else if c.is_alphabetic() || c == '_' {
+            let len = chars[i..]
+                .iter()
+                .take_while(|c| c.is_alphanumeric() || **c == '_')
+                .count();
+            let word: String = chars[i..i + len].iter().collect();
+            let token = match word.as_str() {
+                "let" => Token::Let,
+                "mut" => Token::Mut,
+                "fn" => Token::Fn,
+                "if" => Token::If,
+                "else" => Token::Else,
+                "loop" => Token::Loop,
+                "while" => Token::While,
+                "for" => Token::For,
+                "in" => Token::In,
+                "break" => Token::Break,
+                "continue" => Token::Continue,
+                "return" => Token::Return,
+                "true" => Token::True,
+                "false" => Token::False,
+                _ => Token::Ident(word),
+            };
+            (token, len)
+        } else {
+            let third = chars.get(i + 2).copied();
+            match (c, next, third) {
+                ('.', Some('.'), Some('=')) => (Token::DotDotEq, 3),
+                ('.', Some('.'), _) => (Token::DotDot, 2),
+                ('-', Some('>'), _) => (Token::Arrow, 2),
+                ('+', Some('='), _) => (Token::PlusAssign, 2),
+                ('-', Some('='), _) => (Token::MinusAssign, 2),
+                ('*', Some('='), _) => (Token::StarAssign, 2),
+                ('/', Some('='), _) => (Token::SlashAssign, 2),
+                ('=', Some('='), _) => (Token::EqEq, 2),
+                ('!', Some('='), _) => (Token::NotEq, 2),
+                ('<', Some('='), _) => (Token::Le, 2),
+                ('>', Some('='), _) => (Token::Ge, 2),
+                ('&', Some('&'), _) => (Token::AndAnd, 2),
+                ('|', Some('|'), _) => (Token::OrOr, 2),
+                ('(', ..) => (Token::LParen, 1),
+                (')', ..) => (Token::RParen, 1),
+                ('{', ..) => (Token::LBrace, 1),
+                ('}', ..) => (Token::RBrace, 1),
+                (',', ..) => (Token::Comma, 1),
+                (';', ..) => (Token::Semi, 1),
+                (':', ..) => (Token::Colon, 1),
+                ('.', ..) => (Token::Dot, 1),
+                ('+', ..) => (Token::Plus, 1),
+                ('-', ..) => (Token::Minus, 1),
+                ('*', ..) => (Token::Star, 1),
+                ('/', ..) => (Token::Slash, 1),
+                ('%', ..) => (Token::Percent, 1),
+                ('=', ..) => (Token::Assign, 1),
+                ('<', ..) => (Token::Lt, 1),
+                ('>', ..) => (Token::Gt, 1),
+                ('!', ..) => (Token::Bang, 1),
+                _ => return Err(Error::new(start, format!("unexpected character `{c}`"))),
+            }
+        };
+
+        tokens.push(Spanned { token, pos: start });
+        i += len;
+        pos.column += len;
+    }
+
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(source: &str) -> Vec<Token> {
+        lex(source)
+            .unwrap()
+            .into_iter()
+            .map(|spanned| spanned.token)
+            .collect()
+    }
+
+    #[test]
+    fn keywords_operators_and_numbers() {
+        assert_eq!(
+            tokens("let mut x = 1_000; x += 2 // done"),
+            vec![
+                Token::Let,
+                Token::Mut,
+                Token::Ident(String::from("x")),
+                Token::Assign,
+                Token::Int(1000),
+                Token::Semi,
+                Token::Ident(String::from("x")),
+                Token::PlusAssign,
+                Token::Int(2),
+            ]
+        );
+        assert_eq!(
+            tokens("(1..=4).rev()"),
+            vec![
+                Token::LParen,
+                Token::Int(1),
+                Token::DotDotEq,
+                Token::Int(4),
+                Token::RParen,
+                Token::Dot,
+                Token::Ident(String::from("rev")),
+                Token::LParen,
+                Token::RParen,
+            ]
+        );
+    }
+
+    #[test]
+    fn positions_count_lines_and_columns() {
+        let spanned = lex("{\n    x + 1;\n}").unwrap();
+        let semi = spanned.iter().find(|s| s.token == Token::Semi).unwrap();
+        assert_eq!(
+            semi.pos,
+            Pos {
+                line: 2,
+                column: 10
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_characters() {
+        let error = lex("let x = 3 # 4;").unwrap_err();
+        assert_eq!(
+            error.pos,
+            Pos {
+                line: 1,
+                column: 11
+            }
+        );
+    }
+}
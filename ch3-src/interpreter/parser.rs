@@ -0,0 +1,712 @@
+use super::lexer::{Spanned, Token};
+use super::{Error, Pos};
+use std::rc::Rc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    Neg,
+    Not,
+}
+
+// The types the interpreter knows about. Every integer type is treated the same way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Type {
+    Int,
+    Bool,
+    Unit,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Int(i64),
+    Bool(bool),
+    Unit,
+    Var(String, Pos),
+    Unary(UnaryOp, Box<Expr>, Pos),
+    Binary(BinOp, Box<Expr>, Box<Expr>, Pos),
+    // `x = value`, or `x += value` and friends when `op` is set.
+    Assign {
+        name: String,
+        op: Option<BinOp>,
+        value: Box<Expr>,
+        pos: Pos,
+    },
+    Block(Block),
+    If {
+        condition: Box<Expr>,
+        then: Block,
+        otherwise: Option<Box<Expr>>,
+        pos: Pos,
+    },
+    Loop(Block, Pos),
+    While {
+        condition: Box<Expr>,
+        body: Block,
+        pos: Pos,
+    },
+    // `for var in start..end`, `start..=end` or `(start..end).rev()`.
+    For {
+        var: String,
+        start: Box<Expr>,
+        end: Box<Expr>,
+        inclusive: bool,
+        reversed: bool,
+        body: Block,
+        pos: Pos,
+    },
+    Break(Option<Box<Expr>>, Pos),
+    Continue(Pos),
+    Return(Option<Box<Expr>>, Pos),
+    Call(String, Vec<Expr>, Pos),
+}
+
+impl Expr {
+    // Expressions ending in a block can be used as statements without a semicolon.
+    fn is_block_like(&self) -> bool {
+        matches!(
+            self,
+            Expr::Block(_)
+                | Expr::If { .. }
+                | Expr::Loop(..)
+                | Expr::While { .. }
+                | Expr::For { .. }
+        )
+    }
+}
+
+// `{ statements; tail }`. The block's value is the tail expression, or `()` if there is none.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block {
+    pub stmts: Vec<Stmt>,
+    pub tail: Option<Box<Expr>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Stmt {
+    Let {
+        name: String,
+        mutable: bool,
+        ty: Option<Type>,
+        value: Expr,
+        pos: Pos,
+    },
+    // `semi` is where the closing semicolon is, if the statement has one.
+    Expr {
+        expr: Expr,
+        semi: Option<Pos>,
+    },
+    Fn(Rc<Function>),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Function {
+    pub name: String,
+    pub params: Vec<(String, Type)>,
+    pub returns: Type,
+    pub body: Block,
+    pub pos: Pos,
+}
+
+// Parses a whole program: statements at the top level, optionally ending with an
+// expression whose value is the program's result.
+pub fn parse(tokens: Vec<Spanned>) -> Result<Block, Error> {
+    let mut parser = Parser {
+        tokens,
+        index: 0,
+        depth: 0,
+    };
+    let block = parser.block_body()?;
+    match parser.tokens.get(parser.index) {
+        Some(extra) => Err(Error::new(
+            extra.pos,
+            format!("unexpected `{}`", describe(&extra.token)),
+        )),
+        None => Ok(block),
+    }
+}
+
+struct Parser {
+    tokens: Vec<Spanned>,
+    index: usize,
+    depth: usize,
+}
+
+impl Parser {
+    // How deeply expressions may nest. Each level is about ten nested calls in the
+    // parser, so thousands of `(` are turned away long before the stack runs out.
+    const MAX_DEPTH: usize = 100;
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index).map(|spanned| &spanned.token)
+    }
+
+    fn pos(&self) -> Pos {
+        match self.tokens.get(self.index).or(self.tokens.last()) {
+            Some(spanned) => spanned.pos,
+            None => Pos { line: 1, column: 1 },
+        }
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.index += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &Token) -> Result<Pos, Error> {
+        let pos = self.pos();
+        if self.eat(token) {
+            Ok(pos)
+        } else {
+            Err(self.unexpected(&format!("`{}`", describe(token))))
+        }
+    }
+
+    fn unexpected(&self, expected: &str) -> Error {
+        match self.peek() {
+            Some(token) => Error::new(
+                self.pos(),
+                format!("expected {expected}, found `{}`", describe(token)),
+            ),
+            None => Error::new(
+                self.pos(),
+                format!("expected {expected}, found the end of the input"),
+            ),
+        }
+    }
+
+    // Every nested expression goes through here, whether it is inside brackets, a block,
+    // a chain of unary operators, an `else if` or the value of an assignment.
+    fn nested(&mut self, parse: fn(&mut Parser) -> Result<Expr, Error>) -> Result<Expr, Error> {
+        if self.depth == Parser::MAX_DEPTH {
+            return Err(Error::new(self.pos(), "expression nested too deeply"));
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn ident(&mut self) -> Result<String, Error> {
+        match self.peek() {
+            Some(Token::Ident(name)) => {
+                let name = name.clone();
+                self.index += 1;
+                Ok(name)
+            }
+            _ => Err(self.unexpected("a name")),
+        }
+    }
+
+    fn ty(&mut self) -> Result<Type, Error> {
+        let pos = self.pos();
+        if self.eat(&Token::LParen) {
+            self.expect(&Token::RParen)?;
+            return Ok(Type::Unit);
+        }
+        match self.ident()?.as_str() {
+            "i8" | "i16" | "i32" | "i64" | "i128" | "isize" | "u8" | "u16" | "u32" | "u64"
+            | "u128" | "usize" => Ok(Type::Int),
+            "bool" => Ok(Type::Bool),
+            other => Err(Error::new(pos, format!("unknown type `{other}`"))),
+        }
+    }
+
+    fn block(&mut self) -> Result<Block, Error> {
+        self.expect(&Token::LBrace)?;
+        let block = self.block_body()?;
+        self.expect(&Token::RBrace)?;
+        Ok(block)
+    }
+
+    // Statements up to a closing `}` or the end of the input.
+    fn block_body(&mut self) -> Result<Block, Error> {
+        let mut stmts = Vec::new();
+        loop {
+            match self.peek() {
+                None | Some(Token::RBrace) => return Ok(Block { stmts, tail: None }),
+                Some(Token::Semi) => {
+                    self.index += 1;
+                }
+                Some(Token::Let) => stmts.push(self.let_stmt()?),
+                Some(Token::Fn) => stmts.push(Stmt::Fn(Rc::new(self.function()?))),
+                Some(token) => {
+                    // A statement starting with a block-like expression ends with that
+                    // expression, so `if x { 1 } else { 2 } - 1` is two statements, as in Rust.
+                    let starts_block = matches!(
+                        token,
+                        Token::LBrace | Token::If | Token::Loop | Token::While | Token::For
+                    );
+                    let expr = if starts_block {
+                        self.nested(Parser::primary)?
+                    } else {
+                        self.expr()?
+                    };
+
+                    let semi = self.pos();
+                    if self.eat(&Token::Semi) {
+                        stmts.push(Stmt::Expr {
+                            expr,
+                            semi: Some(semi),
+                        });
+                    } else if matches!(self.peek(), None | Some(Token::RBrace)) {
+                        return Ok(Block {
+                            stmts,
+                            tail: Some(Box::new(expr)),
+                        });
+                    } else if expr.is_block_like() {
+                        stmts.push(Stmt::Expr { expr, semi: None });
+                    } else {
+                        return Err(self.unexpected("`;` or `}`"));
+                    }
+                }
+            }
+        }
+    }
+
+    fn let_stmt(&mut self) -> Result<Stmt, Error> {
+        let pos = self.expect(&Token::Let)?;
+        let mutable = self.eat(&Token::Mut);
+        let name = self.ident()?;
+        let ty = if self.eat(&Token::Colon) {
+            Some(self.ty()?)
+        } else {
+            None
+        };
+        if !self.eat(&Token::Assign) {
+            return Err(self.unexpected("`=` (every `let` needs a value here)"));
+        }
+        let value = self.expr()?;
+        self.expect(&Token::Semi)?;
+        Ok(Stmt::Let {
+            name,
+            mutable,
+            ty,
+            value,
+            pos,
+        })
+    }
+
+    fn function(&mut self) -> Result<Function, Error> {
+        let pos = self.expect(&Token::Fn)?;
+        let name = self.ident()?;
+        self.expect(&Token::LParen)?;
+        let mut params = Vec::new();
+        while !self.eat(&Token::RParen) {
+            let param = self.ident()?;
+            self.expect(&Token::Colon)?;
+            params.push((param, self.ty()?));
+            if !self.eat(&Token::Comma) {
+                self.expect(&Token::RParen)?;
+                break;
+            }
+        }
+        let returns = if self.eat(&Token::Arrow) {
+            self.ty()?
+        } else {
+            Type::Unit
+        };
+        let body = self.block()?;
+        Ok(Function {
+            name,
+            params,
+            returns,
+            body,
+            pos,
+        })
+    }
+
+    fn expr(&mut self) -> Result<Expr, Error> {
+        let pos = self.pos();
+        let target = self.binary(0)?;
+        let op = match self.peek() {
+            Some(Token::Assign) => None,
+            Some(Token::PlusAssign) => Some(BinOp::Add),
+            Some(Token::MinusAssign) => Some(BinOp::Sub),
+            Some(Token::StarAssign) => Some(BinOp::Mul),
+            Some(Token::SlashAssign) => Some(BinOp::Div),
+            _ => return Ok(target),
+        };
+        self.index += 1;
+        let Expr::Var(name, _) = target else {
+            return Err(Error::new(pos, "only a variable can be assigned to"));
+        };
+        let value = self.nested(Parser::expr)?;
+        Ok(Expr::Assign {
+            name,
+            op,
+            value: Box::new(value),
+            pos,
+        })
+    }
+
+    // Precedence climbing: each level binds tighter than the one before.
+    fn binary(&mut self, level: usize) -> Result<Expr, Error> {
+        const LEVELS: &[&[(Token, BinOp)]] = &[
+            &[(Token::OrOr, BinOp::Or)],
+            &[(Token::AndAnd, BinOp::And)],
+            &[
+                (Token::EqEq, BinOp::Eq),
+                (Token::NotEq, BinOp::Ne),
+                (Token::Lt, BinOp::Lt),
+                (Token::Le, BinOp::Le),
+                (Token::Gt, BinOp::Gt),
+                (Token::Ge, BinOp::Ge),
+            ],
+            &[(Token::Plus, BinOp::Add), (Token::Minus, BinOp::Sub)],
+            &[
+                (Token::Star, BinOp::Mul),
+                (Token::Slash, BinOp::Div),
+                (Token::Percent, BinOp::Rem),
+            ],
+        ];
+        if level == LEVELS.len() {
+            return self.unary();
+        }
+
+        let mut left = self.binary(level + 1)?;
+        loop {
+            let pos = self.pos();
+            let Some(&(_, op)) = LEVELS[level]
+                .iter()
+                .find(|(token, _)| self.peek() == Some(token))
+            else {
+                return Ok(left);
+            };
+            self.index += 1;
+            let right = self.binary(level + 1)?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right), pos);
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, Error> {
+        let pos = self.pos();
+        let op = if self.eat(&Token::Minus) {
+            UnaryOp::Neg
+        } else if self.eat(&Token::Bang) {
+            UnaryOp::Not
+        } else {
+            return self.nested(Parser::primary);
+        };
+        Ok(Expr::Unary(op, Box::new(self.nested(Parser::unary)?), pos))
+    }
+
+    fn primary(&mut self) -> Result<Expr, Error> {
+        let pos = self.pos();
+        let Some(token) = self.peek().cloned() else {
+            return Err(self.unexpected("an expression"));
+        };
+        match token {
+            Token::Int(value) => {
+                self.index += 1;
+                Ok(Expr::Int(value))
+            }
+            Token::True | Token::False => {
+                self.index += 1;
+                Ok(Expr::Bool(token == Token::True))
+            }
+            Token::Ident(name) => {
+                self.index += 1;
+                if !self.eat(&Token::LParen) {
+                    return Ok(Expr::Var(name, pos));
+                }
+                let mut args = Vec::new();
+                while !self.eat(&Token::RParen) {
+                    args.push(self.expr()?);
+                    if !self.eat(&Token::Comma) {
+                        self.expect(&Token::RParen)?;
+                        break;
+                    }
+                }
+                Ok(Expr::Call(name, args, pos))
+            }
+            Token::LParen => {
+                self.index += 1;
+                if self.eat(&Token::RParen) {
+                    return Ok(Expr::Unit);
+                }
+                let expr = self.expr()?;
+                self.expect(&Token::RParen)?;
+                Ok(expr)
+            }
+            Token::LBrace => Ok(Expr::Block(self.block()?)),
+            Token::If => self.if_expr(),
+            Token::Loop => {
+                self.index += 1;
+                Ok(Expr::Loop(self.block()?, pos))
+            }
+            Token::While => {
+                self.index += 1;
+                let condition = Box::new(self.expr()?);
+                let body = self.block()?;
+                Ok(Expr::While {
+                    condition,
+                    body,
+                    pos,
+                })
+            }
+            Token::For => self.for_expr(),
+            Token::Break => {
+                self.index += 1;
+                Ok(Expr::Break(self.optional_value()?, pos))
+            }
+            Token::Continue => {
+                self.index += 1;
+                Ok(Expr::Continue(pos))
+            }
+            Token::Return => {
+                self.index += 1;
+                Ok(Expr::Return(self.optional_value()?, pos))
+            }
+            _ => Err(self.unexpected("an expression")),
+        }
+    }
+
+    // The value after `break` or `return`, which can be left out.
+    fn optional_value(&mut self) -> Result<Option<Box<Expr>>, Error> {
+        match self.peek() {
+            None | Some(Token::Semi) | Some(Token::RBrace) => Ok(None),
+            _ => Ok(Some(Box::new(self.expr()?))),
+        }
+    }
+
+    fn if_expr(&mut self) -> Result<Expr, Error> {
+        let pos = self.expect(&Token::If)?;
+        let condition = Box::new(self.expr()?);
+        let then = self.block()?;
+        let otherwise = if !self.eat(&Token::Else) {
+            None
+        } else if self.peek() == Some(&Token::If) {
+            Some(Box::new(self.nested(Parser::if_expr)?))
+        } else {
+            Some(Box::new(Expr::Block(self.block()?)))
+        };
+        Ok(Expr::If {
+            condition,
+            then,
+            otherwise,
+            pos,
+        })
+    }
+
+    fn for_expr(&mut self) -> Result<Expr, Error> {
+        let pos = self.expect(&Token::For)?;
+        let var = self.ident()?;
+        self.expect(&Token::In)?;
+
+        // `(start..end).rev()` needs a look inside the parentheses to tell it apart
+        // from a range that merely starts with a parenthesised expression.
+        let before = self.index;
+        let mut reversed = false;
+        let mut range = None;
+        if self.eat(&Token::LParen) {
+            let start = self.expr()?;
+            if matches!(self.peek(), Some(Token::DotDot | Token::DotDotEq)) {
+                let inclusive = self.range_op()?;
+                let end = self.expr()?;
+                self.expect(&Token::RParen)?;
+                if self.eat(&Token::Dot) {
+                    let method_pos = self.pos();
+                    if self.ident()? != "rev" {
+                        return Err(Error::new(
+                            method_pos,
+                            "only `.rev()` is supported on a range",
+                        ));
+                    }
+                    self.expect(&Token::LParen)?;
+                    self.expect(&Token::RParen)?;
+                    reversed = true;
+                }
+                range = Some((start, end, inclusive));
+            } else {
+                self.index = before;
+            }
+        }
+        let (start, end, inclusive) = match range {
+            Some(range) => range,
+            None => {
+                let start = self.expr()?;
+                let inclusive = self.range_op()?;
+                (start, self.expr()?, inclusive)
+            }
+        };
+
+        let body = self.block()?;
+        Ok(Expr::For {
+            var,
+            start: Box::new(start),
+            end: Box::new(end),
+            inclusive,
+            reversed,
+            body,
+            pos,
+        })
+    }
+
+    // Returns whether the range includes its end.
+    fn range_op(&mut self) -> Result<bool, Error> {
+        if self.eat(&Token::DotDot) {
+            Ok(false)
+        } else if self.eat(&Token::DotDotEq) {
+            Ok(true)
+        } else {
+            Err(self.unexpected("`..` or `..=`"))
+        }
+    }
+}
+
+// How a token is written in source code, for error messages.
+fn describe(token: &Token) -> String {
+    let text = match token {
+        Token::Int(value) => return value.to_string(),
+        Token::Ident(name) => return name.clone(),
+        Token::Let => "let",
+        Token::Mut => "mut",
+        Token::Fn => "fn",
+        Token::If => "if",
+        Token::Else => "else",
+        Token::Loop => "loop",
+        Token::While => "while",
+        Token::For => "for",
+        Token::In => "in",
+        Token::Break => "break",
+        Token::Continue => "continue",
+        Token::Return => "return",
+        Token::True => "true",
+        Token::False => "false",
+        Token::LParen => "(",
+        Token::RParen => ")",
+        Token::LBrace => "{",
+        Token::RBrace => "}",
+        Token::Comma => ",",
+        Token::Semi => ";",
+        Token::Colon => ":",
+        Token::Arrow => "->",
+        Token::Dot => ".",
+        Token::DotDot => "..",
+        Token::DotDotEq => "..=",
+        Token::Plus => "+",
+        Token::Minus => "-",
+        Token::Star => "*",
+        Token::Slash => "/",
+        Token::Percent => "%",
+        Token::Assign => "=",
+        Token::PlusAssign => "+=",
+        Token::MinusAssign => "-=",
+        Token::StarAssign => "*=",
+        Token::SlashAssign => "/=",
+        Token::EqEq => "==",
+        Token::NotEq => "!=",
+        Token::Lt => "<",
+        Token::Le => "<=",
+        Token::Gt => ">",
+        Token::Ge => ">=",
+        Token::AndAnd => "&&",
+        Token::OrOr => "||",
+        Token::Bang => "!",
+    };
+    text.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::lexer::lex;
+
+    fn parse_str(source: &str) -> Result<Block, Error> {
+        parse(lex(source)?)
+    }
+
+    #[test]
+    fn trailing_expression_is_the_tail() {
+        let block = parse_str("let x = 3; x + 1").unwrap();
+        assert_eq!(block.stmts.len(), 1);
+        assert!(matches!(
+            block.tail.as_deref(),
+            Some(Expr::Binary(BinOp::Add, ..))
+        ));
+
+        let block = parse_str("let x = 3; x + 1;").unwrap();
+        assert_eq!(block.stmts.len(), 2);
+        assert!(block.tail.is_none());
+    }
+
+    #[test]
+    fn multiplication_binds_tighter() {
+        let block = parse_str("1 + 2 * 3").unwrap();
+        let Some(Expr::Binary(BinOp::Add, _, right, _)) = block.tail.as_deref() else {
+            panic!("expected an addition");
+        };
+        assert!(matches!(**right, Expr::Binary(BinOp::Mul, ..)));
+    }
+
+    #[test]
+    fn reversed_ranges() {
+        let block = parse_str("for n in (1..4).rev() { n; }").unwrap();
+        assert!(matches!(
+            block.tail.as_deref(),
+            Some(Expr::For {
+                reversed: true,
+                inclusive: false,
+                ..
+            })
+        ));
+        assert!(parse_str("for n in (1 + 1)..=4 {}").is_ok());
+        assert!(parse_str("for n in (1..4).skip() {}").is_err());
+    }
+
+    #[test]
+    fn functions_and_missing_semicolons() {
+        assert!(parse_str("fn five() -> i32 { 5 } five()").is_ok());
+        let error = parse_str("let x = 1 let y = 2;").unwrap_err();
+        assert_eq!(
+            error.pos,
+            Pos {
+                line: 1,
+                column: 11
+            }
+        );
+    }
+
+    #[test]
+    fn deep_nesting_is_an_error() {
+        let depth = Parser::MAX_DEPTH / 2;
+        let source = format!("{}1{}", "(".repeat(depth), ")".repeat(depth));
+        assert!(parse_str(&source).is_ok());
+        let source = format!("{}{{ 2 }}", "if true { 1 } else ".repeat(depth));
+        assert!(parse_str(&source).is_ok());
+        let source = format!("{}1", "x = ".repeat(depth));
+        assert!(parse_str(&source).is_ok());
+
+        for source in [
+            format!("{}1{}", "(".repeat(100_000), ")".repeat(100_000)),
+            format!("{}1{}", "{".repeat(100_000), "}".repeat(100_000)),
+            format!("{}1", "-".repeat(100_000)),
+            format!("{}{{ 2 }}", "if true { 1 } else ".repeat(200_000)),
+            format!("{}1", "x = ".repeat(200_000)),
+        ] {
+            let error = parse_str(&source).unwrap_err();
+            assert!(error.message.contains("nested too deeply"), "{error:?}");
+        }
+    }
+}
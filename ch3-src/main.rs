@@ -11,7 +11,56 @@ Expressions do not include ending semicolons.
 If you add a semicolon to the end of an expression, you turn it into a statement, and it will then not return a value.
 Keep this in mind as you explore function return values and expressions next.
 */
+mod fibonacci;
+mod interpreter;
+mod song;
+mod units;
+
+use std::env;
+use std::process;
+
 fn main() {
+    // `cargo run -- repl` starts an interpreter for the expressions described above, and
+    // `cargo run -- convert 98.6F C` does the chapter's temperature conversion exercise,
+    // `cargo run --release -- fib 1000000` its Fibonacci one and `cargo run -- song` prints
+    // the lyrics of "The Twelve Days of Christmas".
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("repl") => {
+            interpreter::repl();
+            return;
+        }
+        Some("convert") => match units::convert_args(&args[1..]) {
+            Ok(line) => {
+                println!("{line}");
+                return;
+            }
+            Err(err) => {
+                eprintln!("Problem converting: {err}");
+                eprintln!("Usage: convert <value><unit> [to] <unit>, e.g. convert 98.6F C");
+                process::exit(2);
+            }
+        },
+        Some("fib") => {
+            if let Err(err) = fibonacci::run_args(&args[1..]) {
+                eprintln!("Problem computing Fibonacci numbers: {err}");
+                process::exit(2);
+            }
+            return;
+        }
+        Some("song") => match song::run_args(&args[1..]) {
+            Ok(lyrics) => {
+                print!("{lyrics}");
+                return;
+            }
+            Err(err) => {
+                eprintln!("Problem singing: {err}");
+                process::exit(2);
+            }
+        },
+        _ => {}
+    }
+
     let y = {
         let x = 3;
         x + 1
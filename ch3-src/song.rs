@@ -0,0 +1,289 @@
+// Lyrics for cumulative songs such as "The Twelve Days of Christmas", where every verse
+// repeats all the earlier ones backwards: the same `for number in (1..4).rev()` loop as the
+// countdown in `main`. The words come from a template, so any song of this shape can be sung.
+//
+// A template is a list of `key: value` lines, with `#` starting a comment:
+//     title: the song's name
+//     opening: the first line of each verse, with `{ordinal}` where the day goes
+//     and: (optional) how the first gift is sung once other gifts come before it
+//     refrain: (optional) a line ending every verse
+//     verse: <ordinal> | <gift>, once per verse, in order
+use std::fmt::Write;
+use std::fs;
+use std::str::FromStr;
+
+pub const TWELVE_DAYS: &str = include_str!("songs/twelve-days.song");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Markdown,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Song {
+    title: String,
+    opening: String,
+    and: Option<String>,
+    refrain: Option<String>,
+    // The ordinal and gift of each verse.
+    verses: Vec<(String, String)>,
+}
+
+impl Song {
+    pub fn twelve_days() -> Song {
+        TWELVE_DAYS.parse().expect("the built-in song should parse")
+    }
+
+    pub fn days(&self) -> usize {
+        self.verses.len()
+    }
+
+    // The lines of verse `day`, counting from 1. Panics if `day` is 0 or greater than `days()`.
+    pub fn verse(&self, day: usize) -> Vec<String> {
+        let (ordinal, _) = &self.verses[day - 1];
+        let mut lines = vec![self.opening.replace("{ordinal}", ordinal)];
+        for gift in (1..=day).rev() {
+            let line = match &self.and {
+                Some(and) if gift == 1 && day > 1 => and,
+                _ => &self.verses[gift - 1].1,
+            };
+            lines.push(line.clone());
+        }
+        if let Some(refrain) = &self.refrain {
+            lines.push(refrain.clone());
+        }
+        lines
+    }
+
+    // The whole song, with a blank line between verses.
+    pub fn render(&self, format: Format) -> String {
+        let mut out = String::new();
+        match format {
+            Format::Text => writeln!(out, "{}", self.title).unwrap(),
+            Format::Markdown => writeln!(out, "# {}", escape_markdown(&self.title)).unwrap(),
+        }
+        for day in 1..=self.days() {
+            out.push('\n');
+            let lines = self.verse(day);
+            match format {
+                Format::Text => {
+                    for line in &lines {
+                        writeln!(out, "{line}").unwrap();
+                    }
+                }
+                // A backslash at the end of a line keeps the verse's line breaks in Markdown.
+                Format::Markdown => {
+                    let (last, rest) = lines.split_last().unwrap();
+                    for line in rest {
+                        writeln!(out, "{}\\", escape_markdown(line)).unwrap();
+                    }
+                    writeln!(out, "{}", escape_markdown(last)).unwrap();
+                }
+            }
+        }
+        out
+    }
+}
+
+// The command line: `[--markdown] [template file]`, singing the twelve days by default.
+pub fn run_args(args: &[String]) -> Result<String, String> {
+    let (format, rest) = match args {
+        [flag, rest @ ..] if flag == "--markdown" => (Format::Markdown, rest),
+        rest => (Format::Text, rest),
+    };
+    let song = match rest {
+        [] => Song::twelve_days(),
+        [path] => fs::read_to_string(path)
+            .map_err(|err| format!("cannot read {path}: {err}"))?
+            .parse()
+            .map_err(|err| format!("{path}: {err}"))?,
+        _ => return Err(String::from("expected [--markdown] [template file]")),
+    };
+    Ok(song.render(format))
+}
+
+fn escape_markdown(line: &str) -> String {
+    let mut escaped = String::with_capacity(line.len());
+    for c in line.chars() {
+        if "\\`*_[]#<>|".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+impl FromStr for Song {
+    type Err = String;
+
+    fn from_str(template: &str) -> Result<Song, String> {
+        let mut title = None;
+        let mut opening = None;
+        let mut and = None;
+        let mut refrain = None;
+        let mut verses = Vec::new();
+
+        for (index, line) in template.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |message: String| format!("line {}: {message}", index + 1);
+            let (key, value) = line
+                .split_once(':')
+                .ok_or_else(|| error(String::from("expected `key: value`")))?;
+            let (key, value) = (key.trim(), value.trim().to_string());
+            let slot = match key {
+                "title" => &mut title,
+                "opening" => &mut opening,
+                "and" => &mut and,
+                "refrain" => &mut refrain,
+                "verse" => {
+                    let (ordinal, gift) = value.split_once('|').ok_or_else(|| {
+                        error(String::from("expected `verse: <ordinal> | <gift>`"))
+                    })?;
+                    verses.push((ordinal.trim().to_string(), gift.trim().to_string()));
+                    continue;
+                }
+                other => return Err(error(format!("unknown key `{other}`"))),
+            };
+            if slot.replace(value).is_some() {
+                return Err(error(format!("`{key}` is given more than once")));
+            }
+        }
+
+        let title = title.ok_or("the template has no `title`")?;
+        let opening = opening.ok_or("the template has no `opening`")?;
+        if !opening.contains("{ordinal}") {
+            return Err(String::from("the `opening` has no `{ordinal}` in it"));
+        }
+        if verses.is_empty() {
+            return Err(String::from("the template has no verses"));
+        }
+        Ok(Song {
+            title,
+            opening,
+            and,
+            refrain,
+            verses,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_and_second_verses() {
+        let song = Song::twelve_days();
+        assert_eq!(song.days(), 12);
+        assert_eq!(
+            song.verse(1),
+            [
+                "On the first day of Christmas my true love sent to me",
+                "A partridge in a pear tree",
+            ]
+        );
+        assert_eq!(
+            song.verse(2),
+            [
+                "On the second day of Christmas my true love sent to me",
+                "Two turtle doves",
+                "And a partridge in a pear tree",
+            ]
+        );
+    }
+
+    #[test]
+    fn last_verse_counts_down_every_gift() {
+        let verse = Song::twelve_days().verse(12).join("\n");
+        assert_eq!(
+            verse,
+            "On the twelfth day of Christmas my true love sent to me
+Twelve drummers drumming
+Eleven pipers piping
+Ten lords a-leaping
+Nine ladies dancing
+Eight maids a-milking
+Seven swans a-swimming
+Six geese a-laying
+Five gold rings
+Four calling birds
+Three French hens
+Two turtle doves
+And a partridge in a pear tree"
+        );
+    }
+
+    const COUNTING: &str = "
+        # A made-up song with a refrain and no `and`.
+        title: Counting *Stars*
+        opening: Star number {ordinal}
+        refrain: Shine on
+        verse: one | A small star
+        verse: two | A bright star
+    ";
+
+    #[test]
+    fn renders_text() {
+        let song: Song = COUNTING.parse().unwrap();
+        assert_eq!(
+            song.render(Format::Text),
+            "Counting *Stars*
+
+Star number one
+A small star
+Shine on
+
+Star number two
+A bright star
+A small star
+Shine on
+"
+        );
+    }
+
+    #[test]
+    fn renders_markdown() {
+        let song: Song = COUNTING.parse().unwrap();
+        assert_eq!(
+            song.render(Format::Markdown),
+            "# Counting \\*Stars\\*
+
+Star number one\\
+A small star\\
+Shine on
+
+Star number two\\
+A bright star\\
+A small star\\
+Shine on
+"
+        );
+    }
+
+    #[test]
+    fn rejects_broken_templates() {
+        let error = |template: &str| template.parse::<Song>().unwrap_err();
+        assert_eq!(error("title: x\nbogus"), "line 2: expected `key: value`");
+        assert_eq!(
+            error("title: x\ntitle: y"),
+            "line 2: `title` is given more than once"
+        );
+        assert_eq!(error("name: x"), "line 1: unknown key `name`");
+        assert_eq!(
+            error("title: x\nverse: one"),
+            "line 2: expected `verse: <ordinal> | <gift>`"
+        );
+        assert_eq!(
+            error("title: x\nopening: hi\nverse: a | b"),
+            "the `opening` has no `{ordinal}` in it"
+        );
+        assert_eq!(
+            error("title: x\nopening: {ordinal}"),
+            "the template has no verses"
+        );
+    }
+}
@@ -0,0 +1,541 @@
+// Unit conversion, starting from the chapter's exercise of converting between Fahrenheit
+// and Celsius. Values are kept as exact fractions, so 98.6 °F becomes exactly 37 °C instead
+// of 36.99999999999999; only a result that has no exact decimal form is rounded for display.
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+// A fraction `num / den` in lowest terms, with a positive denominator. Neither part is
+// ever `i128::MIN`, so negating one or taking its absolute value cannot overflow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ratio {
+    num: i128,
+    den: i128,
+}
+
+fn gcd(a: i128, b: i128) -> u128 {
+    let (mut a, mut b) = (a.unsigned_abs(), b.unsigned_abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+const TOO_LARGE: &str = "the number is too large to convert exactly";
+
+impl Ratio {
+    pub const ZERO: Ratio = Ratio { num: 0, den: 1 };
+
+    pub fn new(num: i128, den: i128) -> Ratio {
+        if den == 0 {
+            panic!("Ratio denominator must not be zero.");
+        }
+        Ratio::reduce(num, den).expect(TOO_LARGE)
+    }
+
+    // Brings `num / den` to lowest terms. The signs are worked out on the magnitudes, so
+    // `i128::MIN` in either part is an error rather than an overflow.
+    fn reduce(num: i128, den: i128) -> Result<Ratio, String> {
+        let divisor = gcd(num, den).max(1);
+        let magnitude = |part: i128| i128::try_from(part.unsigned_abs() / divisor);
+        match (magnitude(num), magnitude(den)) {
+            (Ok(n), Ok(d)) => Ok(Ratio {
+                num: if (num < 0) != (den < 0) { -n } else { n },
+                den: d,
+            }),
+            _ => Err(String::from(TOO_LARGE)),
+        }
+    }
+
+    pub fn add(self, other: Ratio) -> Result<Ratio, String> {
+        let num = self
+            .num
+            .checked_mul(other.den)
+            .zip(other.num.checked_mul(self.den))
+            .and_then(|(a, b)| a.checked_add(b));
+        let den = self.den.checked_mul(other.den);
+        match (num, den) {
+            (Some(num), Some(den)) => Ratio::reduce(num, den),
+            _ => Err(String::from(TOO_LARGE)),
+        }
+    }
+
+    pub fn sub(self, other: Ratio) -> Result<Ratio, String> {
+        let num = other
+            .num
+            .checked_neg()
+            .ok_or_else(|| String::from(TOO_LARGE))?;
+        self.add(Ratio {
+            num,
+            den: other.den,
+        })
+    }
+
+    pub fn mul(self, other: Ratio) -> Result<Ratio, String> {
+        // Cancelling across first keeps the intermediate numbers small. Both gcds divide a
+        // part that is not `i128::MIN`, so they fit in an i128.
+        let a = gcd(self.num, other.den).max(1) as i128;
+        let b = gcd(other.num, self.den).max(1) as i128;
+        let num = (self.num / a).checked_mul(other.num / b);
+        let den = (self.den / b).checked_mul(other.den / a);
+        match (num, den) {
+            (Some(num), Some(den)) => Ratio::reduce(num, den),
+            _ => Err(String::from(TOO_LARGE)),
+        }
+    }
+
+    pub fn div(self, other: Ratio) -> Result<Ratio, String> {
+        if other.num == 0 {
+            return Err(String::from("division by zero"));
+        }
+        self.mul(Ratio::reduce(other.den, other.num)?)
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.num as f64 / self.den as f64
+    }
+
+    // A fraction has a finite decimal form when its denominator has no prime factors but 2 and 5.
+    fn terminates(self) -> bool {
+        let mut den = self.den;
+        for p in [2, 5] {
+            while den % p == 0 {
+                den /= p;
+            }
+        }
+        den == 1
+    }
+}
+
+impl PartialOrd for Ratio {
+    fn partial_cmp(&self, other: &Ratio) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Ratio {
+    fn cmp(&self, other: &Ratio) -> Ordering {
+        // Denominators are positive, so cross-multiplying keeps the order. The products
+        // can overflow for huge values, in which case the floating-point value decides.
+        match (
+            self.num.checked_mul(other.den),
+            other.num.checked_mul(self.den),
+        ) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            _ => self.to_f64().total_cmp(&other.to_f64()),
+        }
+    }
+}
+
+// Exact decimals are shown in full, anything else rounded to six places with a `~` in front.
+// So is an exact decimal whose long division would overflow, which needs a denominator
+// near the top of the i128 range.
+impl fmt::Display for Ratio {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let rounded = || trim_zeros(format!("{:.6}", self.to_f64()));
+        if !self.terminates() {
+            return write!(f, "~{}", rounded());
+        }
+        let sign = if self.num < 0 { "-" } else { "" };
+        let (whole, mut rest) = (self.num.abs() / self.den, self.num.abs() % self.den);
+        let mut digits = String::new();
+        while rest != 0 {
+            let Some(shifted) = rest.checked_mul(10) else {
+                return write!(f, "~{}", rounded());
+            };
+            digits.push(char::from(b'0' + (shifted / self.den) as u8));
+            rest = shifted % self.den;
+        }
+        if digits.is_empty() {
+            write!(f, "{sign}{whole}")
+        } else {
+            write!(f, "{sign}{whole}.{digits}")
+        }
+    }
+}
+
+fn trim_zeros(text: String) -> String {
+    if text.contains('.') {
+        text.trim_end_matches('0').trim_end_matches('.').to_string()
+    } else {
+        text
+    }
+}
+
+// Reads a decimal number such as `98.6`, `-40` or `.5` exactly.
+impl FromStr for Ratio {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Ratio, String> {
+        let invalid = || format!("`{s}` is not a number");
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        if whole.is_empty() && fraction.is_empty() {
+            return Err(invalid());
+        }
+        if !whole
+            .chars()
+            .chain(fraction.chars())
+            .all(|c| c.is_ascii_digit())
+        {
+            return Err(invalid());
+        }
+
+        let mut num: i128 = 0;
+        let mut den: i128 = 1;
+        for c in whole.chars().chain(fraction.chars()) {
+            num = num
+                .checked_mul(10)
+                .and_then(|n| n.checked_add(c as i128 - '0' as i128))
+                .ok_or_else(|| String::from(TOO_LARGE))?;
+        }
+        for _ in fraction.chars() {
+            den = den.checked_mul(10).ok_or_else(|| String::from(TOO_LARGE))?;
+        }
+        Ok(Ratio::new(if negative { -num } else { num }, den))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dimension {
+    Temperature,
+    Length,
+    Mass,
+    Time,
+}
+
+impl fmt::Display for Dimension {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Dimension::Temperature => "temperature",
+            Dimension::Length => "length",
+            Dimension::Mass => "mass",
+            Dimension::Time => "time",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    Celsius,
+    Fahrenheit,
+    Kelvin,
+    Millimeter,
+    Centimeter,
+    Meter,
+    Kilometer,
+    Inch,
+    Foot,
+    Yard,
+    Mile,
+    Milligram,
+    Gram,
+    Kilogram,
+    Ounce,
+    Pound,
+    Second,
+    Minute,
+    Hour,
+    Day,
+}
+
+// Each unit: its symbol, the other names it can be written as, its dimension, and
+// how many of the dimension's base unit (kelvin, meter, kilogram, second) one of it is.
+// Every factor here is exact by definition, such as the international inch of 25.4 mm.
+type UnitEntry = (
+    Unit,
+    &'static str,
+    &'static [&'static str],
+    Dimension,
+    (i128, i128),
+);
+
+#[rustfmt::skip]
+const UNITS: &[UnitEntry] = &[
+    (Unit::Celsius, "°C", &["C", "celsius"], Dimension::Temperature, (1, 1)),
+    (Unit::Fahrenheit, "°F", &["F", "fahrenheit"], Dimension::Temperature, (5, 9)),
+    (Unit::Kelvin, "K", &["kelvin"], Dimension::Temperature, (1, 1)),
+    (Unit::Millimeter, "mm", &["millimeter", "millimeters"], Dimension::Length, (1, 1000)),
+    (Unit::Centimeter, "cm", &["centimeter", "centimeters"], Dimension::Length, (1, 100)),
+    (Unit::Meter, "m", &["meter", "meters"], Dimension::Length, (1, 1)),
+    (Unit::Kilometer, "km", &["kilometer", "kilometers"], Dimension::Length, (1000, 1)),
+    (Unit::Inch, "in", &["inch", "inches"], Dimension::Length, (254, 10_000)),
+    (Unit::Foot, "ft", &["foot", "feet"], Dimension::Length, (3048, 10_000)),
+    (Unit::Yard, "yd", &["yard", "yards"], Dimension::Length, (9144, 10_000)),
+    (Unit::Mile, "mi", &["mile", "miles"], Dimension::Length, (1_609_344, 1000)),
+    (Unit::Milligram, "mg", &["milligram", "milligrams"], Dimension::Mass, (1, 1_000_000)),
+    (Unit::Gram, "g", &["gram", "grams"], Dimension::Mass, (1, 1000)),
+    (Unit::Kilogram, "kg", &["kilogram", "kilograms"], Dimension::Mass, (1, 1)),
+    (Unit::Ounce, "oz", &["ounce", "ounces"], Dimension::Mass, (45_359_237, 1_600_000_000)),
+    (Unit::Pound, "lb", &["pound", "pounds", "lbs"], Dimension::Mass, (45_359_237, 100_000_000)),
+    (Unit::Second, "s", &["sec", "second", "seconds"], Dimension::Time, (1, 1)),
+    (Unit::Minute, "min", &["minute", "minutes"], Dimension::Time, (60, 1)),
+    (Unit::Hour, "h", &["hr", "hour", "hours"], Dimension::Time, (3600, 1)),
+    (Unit::Day, "d", &["day", "days"], Dimension::Time, (86_400, 1)),
+];
+
+impl Unit {
+    fn entry(self) -> &'static UnitEntry {
+        UNITS.iter().find(|entry| entry.0 == self).unwrap()
+    }
+
+    pub fn symbol(self) -> &'static str {
+        self.entry().1
+    }
+
+    pub fn dimension(self) -> Dimension {
+        self.entry().3
+    }
+
+    fn factor(self) -> Ratio {
+        let (num, den) = self.entry().4;
+        Ratio::new(num, den)
+    }
+
+    // Temperature scales do not all start at the same zero, so converting one is a
+    // multiplication and an addition: kelvin = (value + offset) * factor.
+    fn offset(self) -> Ratio {
+        match self {
+            Unit::Celsius => Ratio::new(27_315, 100),
+            Unit::Fahrenheit => Ratio::new(45_967, 100),
+            _ => Ratio::ZERO,
+        }
+    }
+
+    fn to_base(self, value: Ratio) -> Result<Ratio, String> {
+        value.add(self.offset())?.mul(self.factor())
+    }
+
+    fn out_of_base(self, value: Ratio) -> Result<Ratio, String> {
+        value.div(self.factor())?.sub(self.offset())
+    }
+}
+
+impl FromStr for Unit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Unit, String> {
+        let name = s.trim();
+        UNITS
+            .iter()
+            .find(|(_, symbol, names, ..)| {
+                *symbol == name
+                    || symbol.trim_start_matches('°') == name
+                    || names.iter().any(|n| n.eq_ignore_ascii_case(name))
+            })
+            .map(|entry| entry.0)
+            .ok_or_else(|| format!("unknown unit `{name}`"))
+    }
+}
+
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quantity {
+    pub value: Ratio,
+    pub unit: Unit,
+}
+
+impl Quantity {
+    // Refuses values that cannot exist: temperatures below absolute zero and negative
+    // lengths, masses or durations.
+    pub fn new(value: Ratio, unit: Unit) -> Result<Quantity, String> {
+        let quantity = Quantity { value, unit };
+        if unit.to_base(value)? < Ratio::ZERO {
+            return Err(match unit.dimension() {
+                Dimension::Temperature => format!("{quantity} is below absolute zero"),
+                dimension => format!("a {dimension} cannot be negative, got {quantity}"),
+            });
+        }
+        Ok(quantity)
+    }
+
+    pub fn convert(self, to: Unit) -> Result<Quantity, String> {
+        if self.unit.dimension() != to.dimension() {
+            return Err(format!(
+                "cannot convert {} ({}) to {} ({})",
+                self.unit,
+                self.unit.dimension(),
+                to,
+                to.dimension()
+            ));
+        }
+        let value = to.out_of_base(self.unit.to_base(self.value)?)?;
+        Ok(Quantity { value, unit: to })
+    }
+}
+
+impl fmt::Display for Quantity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Degrees are written straight after the number, other units after a space.
+        if self.unit.symbol().starts_with('°') {
+            write!(f, "{}{}", self.value, self.unit)
+        } else {
+            write!(f, "{} {}", self.value, self.unit)
+        }
+    }
+}
+
+// Accepts a number followed by a unit, with or without a space: `98.6F`, `12 km`, `-40 °C`.
+impl FromStr for Quantity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Quantity, String> {
+        let s = s.trim();
+        let split = s
+            .char_indices()
+            .find(|&(i, c)| !(c.is_ascii_digit() || c == '.' || (i == 0 && (c == '-' || c == '+'))))
+            .map(|(i, _)| i)
+            .ok_or_else(|| format!("`{s}` has no unit"))?;
+        let (number, unit) = s.split_at(split);
+        if number.is_empty() {
+            return Err(format!("`{s}` does not start with a number"));
+        }
+        Quantity::new(number.parse()?, unit.parse()?)
+    }
+}
+
+// The command-line converter: `98.6F C`, `12 km to mi` or `12 km mi`. The value and its
+// unit may come as one argument or two, since the shell splits `12 km` unless it is quoted.
+pub fn convert_args(args: &[String]) -> Result<String, String> {
+    let mut words: Vec<&str> = args.iter().map(String::as_str).collect();
+    let target = words.pop().ok_or("nothing to convert")?;
+    if words.last() == Some(&"to") {
+        words.pop();
+    }
+    if words.is_empty() {
+        return Err(String::from("missing the unit to convert to"));
+    }
+    let from: Quantity = words.join(" ").parse()?;
+    let to = from.convert(target.parse()?)?;
+    Ok(format!("{from} = {to}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn q(s: &str) -> Quantity {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn body_temperature_is_exact() {
+        let celsius = q("98.6F").convert(Unit::Celsius).unwrap();
+        assert_eq!(celsius.value, Ratio::new(37, 1));
+        assert_eq!(celsius.to_string(), "37°C");
+        assert_eq!(
+            q("-40 °C").convert(Unit::Fahrenheit).unwrap().to_string(),
+            "-40°F"
+        );
+        assert_eq!(
+            q("0 K").convert(Unit::Celsius).unwrap().to_string(),
+            "-273.15°C"
+        );
+    }
+
+    #[test]
+    fn lengths_masses_and_times() {
+        assert_eq!(
+            q("12 km").convert(Unit::Meter).unwrap().to_string(),
+            "12000 m"
+        );
+        assert_eq!(
+            q("1 mi").convert(Unit::Foot).unwrap().to_string(),
+            "5280 ft"
+        );
+        assert_eq!(q("1 lb").convert(Unit::Ounce).unwrap().to_string(), "16 oz");
+        assert_eq!(
+            q("1 lb").convert(Unit::Kilogram).unwrap().to_string(),
+            "0.45359237 kg"
+        );
+        assert_eq!(
+            q("90 min").convert(Unit::Hour).unwrap().to_string(),
+            "1.5 h"
+        );
+        assert_eq!(
+            q("1 km").convert(Unit::Mile).unwrap().to_string(),
+            "~0.621371 mi"
+        );
+    }
+
+    #[test]
+    fn round_trips_are_exact() {
+        let start = q("212F");
+        let back = start
+            .convert(Unit::Kelvin)
+            .and_then(|k| k.convert(Unit::Celsius))
+            .and_then(|c| c.convert(Unit::Fahrenheit))
+            .unwrap();
+        assert_eq!(back, start);
+    }
+
+    #[test]
+    fn rejects_impossible_values() {
+        assert!("-500F"
+            .parse::<Quantity>()
+            .unwrap_err()
+            .contains("absolute zero"));
+        assert!("-1 K".parse::<Quantity>().is_err());
+        assert!("-273.15 C".parse::<Quantity>().is_ok());
+        assert!("-273.16 C".parse::<Quantity>().is_err());
+        assert!("-3 kg".parse::<Quantity>().is_err());
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        assert!("12".parse::<Quantity>().is_err());
+        assert!("km".parse::<Quantity>().is_err());
+        assert!("12 furlongs".parse::<Quantity>().is_err());
+        assert!("1.2.3 m".parse::<Quantity>().is_err());
+        assert!(q("3 kg").convert(Unit::Meter).is_err());
+    }
+
+    #[test]
+    fn command_line() {
+        let args = |line: &str| -> Vec<String> { line.split(' ').map(String::from).collect() };
+        assert_eq!(
+            convert_args(&args("98.6F C")),
+            Ok(String::from("98.6°F = 37°C"))
+        );
+        assert_eq!(
+            convert_args(&args("12 km to m")),
+            Ok(String::from("12 km = 12000 m"))
+        );
+        assert!(convert_args(&args("12 km")).is_err());
+        assert!(convert_args(&[]).is_err());
+    }
+
+    #[test]
+    fn ratio_arithmetic() {
+        let third = Ratio::new(1, 3);
+        assert_eq!(third.add(third).unwrap(), Ratio::new(2, 3));
+        assert_eq!(Ratio::new(-2, -4), Ratio::new(1, 2));
+        assert_eq!(third.to_string(), "~0.333333");
+        assert_eq!(".5".parse::<Ratio>(), Ok(Ratio::new(1, 2)));
+        assert!(Ratio::new(i128::MAX, 1).add(Ratio::new(1, 1)).is_err());
+        assert!(Ratio::new(-i128::MAX, 1).sub(Ratio::new(1, 1)).is_err());
+        assert!(Ratio::reduce(i128::MIN, 1).is_err());
+        assert_eq!(Ratio::reduce(i128::MIN, 2), Ok(Ratio::new(-(1 << 126), 1)));
+        assert!(third.div(Ratio::ZERO).is_err());
+    }
+
+    #[test]
+    fn huge_denominators_are_rounded() {
+        assert_eq!(
+            convert_args(&[
+                String::from("0.99999999999999999999999999999999999999m"),
+                String::from("m"),
+            ]),
+            Ok(String::from("~1 m = ~1 m"))
+        );
+    }
+}
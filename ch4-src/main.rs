@@ -11,19 +11,58 @@
 // Because the pointer to the heap is a known, fixed size, you can store the pointer on the stack,
 // but when you want the actual data, you must follow the pointer.
 
+mod rope;
+mod strings;
+mod text;
+mod traced;
+mod tracking;
+
+use rope::Rope;
+use std::env;
+use std::fmt::Display;
+use strings::{CowString, SmallString};
+use traced::{Timeline, Traced};
+use tracking::{CountingAllocator, Tracker};
+
+// Every allocation and free goes through this counter, and the `[heap]` lines that
+// `ownership` prints show what each step below did to the heap.
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
 fn main() {
+    // `cargo run --release -- rope-bench` compares editing a `String` with editing a `Rope`.
+    if env::args().nth(1).as_deref() == Some("rope-bench") {
+        rope::bench::run();
+        return;
+    }
+
+    ownership();
+    // Every value `ownership` made has gone out of scope by now, so whatever is left was
+    // allocated by something that lives for the whole program, such as the stdout buffer.
+    println!(
+        "Still on the heap after the examples: {} bytes",
+        tracking::totals().bytes_live()
+    );
+}
+
+fn ownership() {
+    let mut heap = Tracker::start();
     let s = "hello";
+    heap.report("let s = \"hello\"");
     // String literals are convenient, but they aren’t suitable for every situation in which we may want to use text.
     // One reason is that they’re immutable. Another is that not every string value can be known when we write our code:
     // for example, what if we want to take user input and store it?
     // For these situations, Rust has a second string type, `String`.
     let s = String::from("hello");
+    heap.report("let s = String::from(\"hello\")");
     // The double colon :: operator allows us to namespace this particular `from` function under the String type
     // rather than using some sort of name like `string_from`.
 
     // This kind of string can be mutated:
     let mut s = String::from("hello");
+    heap.report("let mut s = String::from(\"hello\")");
     s.push_str(", world!"); // push_str() appends a literal to a String
+    heap.report("s.push_str(\", world!\")");
     println!("{s}"); // This will print `hello, world!`
 
     // In the case of a string literal, we know the contents at compile time, so the text is hardcoded directly into the final executable.
@@ -40,11 +79,14 @@ fn main() {
 
     let x = 5;
     let y = x;
+    heap.report("let y = x");
     // We can probably guess what this is doing: “bind the value 5 to x;
     // then make a copy of the value in x and bind it to y.” We now have two variables, x and y, and both equal 5.
 
     let s1 = String::from("hello");
+    heap.report("let s1 = String::from(\"hello\")");
     let s2 = s1;
+    heap.report("let s2 = s1");
     // This looks very similar, so we might assume that the way it works would be the same:
     // that is, the second line would make a copy of the value in s1 and bind it to s2.
     // But this isn’t quite what happens.
@@ -55,9 +97,71 @@ fn main() {
     // If a type implements the Copy trait, variables that use it do not move, but rather are trivially copied, making them still valid after assignment to another variable.
 
     let s = String::from("hello"); // s comes into scope
+    heap.report("let s = String::from(\"hello\")");
     takes_ownership(s); // s's value moves into the function... and so is no longer valid here
+    heap.report("takes_ownership(s)");
     let x = 5; // x comes into scope
     makes_copy(x); // x would move into the function, but i32 is Copy, so it's okay to still use x afterward
+    heap.report("makes_copy(x)");
+
+    // A SmallString keeps short text inside the value itself, like an integer, so the same
+    // steps leave the heap alone, until it grows too long to fit.
+    let mut small = SmallString::from("hello");
+    heap.report("let small = SmallString::from(\"hello\")");
+    takes_ownership_of(small.clone());
+    heap.report("takes_ownership_of(small.clone())");
+    small.push_str(", a string too long to fit");
+    heap.report("small.push_str(...)");
+    println!("small is inline: {}", small.is_inline());
+
+    // A CowString shares its text between clones: only the first one allocates, and dropping
+    // a clone frees nothing while another still uses the text.
+    let mut shared = CowString::from("hello");
+    heap.report("let shared = CowString::from(\"hello\")");
+    takes_ownership_of(shared.clone());
+    heap.report("takes_ownership_of(shared.clone())");
+    let copy = shared.clone();
+    shared.push_str(", world"); // copy still needs "hello", so this copies the text first
+    heap.report("shared.push_str(\", world\") while shared");
+    println!("shared: {shared}, copy: {copy}, still shared: {}", copy.is_shared());
+
+    trace_ownership();
+
+    // The Slice Type:
+    // A string slice is a reference to part of a String, so it borrows the text instead of copying it.
+    let s = String::from("hello world");
+    let word = text::first_word(&s); // word is a &str pointing into s
+    println!("The first word of {s:?} is {word:?}, the last is {:?}", text::last_word(&s));
+    // Slice ranges count bytes, and must start and end on character boundaries:
+    // &"你好"[0..1] would panic, because 你 takes three bytes. Counting characters is always safe.
+    let hello = "你好";
+    println!(
+        "Bytes 0..1 of {hello:?}: {:?}, characters 0..1: {:?}",
+        hello.get(0..1),
+        text::char_slice(hello, 0, 1)
+    );
+    // Some characters a reader sees are several chars: "é" can be `e` plus a combining accent.
+    let cafe = "cafe\u{301} au lait";
+    println!(
+        "The second word of {cafe:?} is {:?}; its first 4 chars are {:?}, its first 4 graphemes {:?}",
+        text::nth_word(cafe, 1),
+        text::char_slice(cafe, 0, 4),
+        text::grapheme_slice(cafe, 0, 4)
+    );
+
+    // For long texts that are edited all over, a rope keeps the text in small chunks,
+    // so an insert in the middle does not move everything after it.
+    let mut rope = Rope::from("first line\nthird line\n");
+    rope.insert(11, "second line\n");
+    rope.push_str("fourth line");
+    rope.remove(0..6); // "line\nsecond line\n..."
+    println!(
+        "The rope has {} lines; line 1 is {:?}, char 0 is {:?}, and the first 4 chars are {:?}",
+        rope.len_lines(),
+        rope.line(1).map(|line| line.to_string()),
+        rope.char(0),
+        String::from(&rope.slice(0..4))
+    );
 } // Here, x goes out of scope, then s. But because s's value was moved, nothing special happens.
 
 fn takes_ownership(some_string: String) {
@@ -65,7 +169,88 @@ fn takes_ownership(some_string: String) {
     println!("{some_string}");
 } // Here, some_string goes out of scope and `drop` is called. The backing memory is freed.
 
+// takes_ownership for any string type, to compare what dropping each one does.
+fn takes_ownership_of<T: Display>(some_string: T) {
+    println!("{some_string}");
+}
+
 fn makes_copy(some_integer: i32) {
     // some_integer comes into scope
     println!("{some_integer}");
 } // Here, some_integer goes out of scope. Nothing special happens.
+
+// The same moves again with `Traced` values, which log each step, so the timeline shows
+// that `s1` is never dropped and in which order the rest go at the closing bracket.
+fn trace_ownership() {
+    let timeline = Timeline::start();
+    let id;
+    {
+        let s1 = Traced::new("s1", String::from("hello"));
+        id = s1.id();
+        let s2 = s1.hand_off("s2"); // let s2 = s1;
+        let s3 = s2.clone_as("s3"); // let s3 = s2.clone();
+        println!("s2 = {}, s3 = {}", *s2, *s3);
+
+        let s = Traced::new("s", String::from("hello"));
+        takes_traced_ownership(s.hand_off("some_string")); // takes_ownership(s);
+
+        // What the closing bracket does, written out so the timeline shows this line:
+        // s3 and then s2 are dropped; s1 and s were moved, so nothing happens for them.
+        s3.drop_here();
+        s2.drop_here();
+    }
+    println!("Ownership timeline:");
+    print!("{}", timeline.render());
+    println!(
+        "The string created as s1 was dropped {} time(s).",
+        timeline.drops_of(id)
+    );
+}
+
+fn takes_traced_ownership(some_string: Traced<String>) {
+    println!("{}", *some_string);
+    some_string.drop_here(); // Here, some_string is dropped, before the caller's scope ends.
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracking::track;
+
+    // The first print may allocate the buffer output goes to, which would otherwise be
+    // counted against whatever a test measures next.
+    fn warm_up_stdout() {
+        println!();
+    }
+
+    #[test]
+    fn takes_ownership_frees_the_string() {
+        let s = String::from("hello");
+        warm_up_stdout();
+        let ((), stats) = track(|| takes_ownership(s));
+        assert_eq!(stats.deallocations, 1);
+        assert_eq!(stats.bytes_freed, 5);
+    }
+
+    #[test]
+    fn each_value_is_dropped_once() {
+        let timeline = Timeline::start();
+        let (id1, id2);
+        {
+            let s1 = Traced::new("s1", String::from("hello"));
+            id1 = s1.id();
+            let s2 = s1.hand_off("s2");
+            id2 = s2.clone_as("s3").id();
+            takes_traced_ownership(s2);
+        }
+        assert_eq!(timeline.drops_of(id1), 1);
+        assert_eq!(timeline.drops_of(id2), 1);
+    }
+
+    #[test]
+    fn makes_copy_does_not_allocate() {
+        warm_up_stdout();
+        let ((), stats) = track(|| makes_copy(5));
+        assert!(stats.is_empty(), "makes_copy caused {stats}");
+    }
+}
@@ -0,0 +1,512 @@
+// A rope keeps a long text as a balanced tree of small strings (chunks), instead of one
+// long buffer like `String`. Inserting into the middle of a `String` moves every byte after
+// that point, and growing it may copy the whole text to a bigger allocation; a rope only
+// splits and rebuilds the O(log n) nodes on the path to the edit.
+//
+// Nodes are shared with `Rc`, so an edit or a slice builds new nodes along one path and
+// reuses the rest of the tree, and cloning a rope is cheap. Positions are counted in chars,
+// so an edit can never land inside a multi-byte character.
+pub mod bench;
+
+use std::fmt;
+use std::ops::Range;
+use std::rc::Rc;
+
+// Chunks are kept below this many bytes. Small chunks make edits cheap; big ones keep the
+// tree shallow.
+const MAX_CHUNK: usize = 1024;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Metrics {
+    bytes: usize,
+    chars: usize,
+    newlines: usize,
+}
+
+impl Metrics {
+    fn of(text: &str) -> Metrics {
+        Metrics {
+            bytes: text.len(),
+            chars: text.chars().count(),
+            newlines: text.bytes().filter(|&b| b == b'\n').count(),
+        }
+    }
+
+    fn add(self, other: Metrics) -> Metrics {
+        Metrics {
+            bytes: self.bytes + other.bytes,
+            chars: self.chars + other.chars,
+            newlines: self.newlines + other.newlines,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Kind {
+    Leaf(String),
+    Branch(Link, Link),
+}
+
+// Each node knows the size of everything below it, which is what lets a lookup go straight
+// down the tree. `height` is 0 for a leaf and keeps the tree balanced like an AVL tree.
+#[derive(Debug)]
+struct Node {
+    metrics: Metrics,
+    height: u8,
+    kind: Kind,
+}
+
+type Link = Rc<Node>;
+
+fn leaf(text: String) -> Link {
+    Rc::new(Node {
+        metrics: Metrics::of(&text),
+        height: 0,
+        kind: Kind::Leaf(text),
+    })
+}
+
+fn branch(left: Link, right: Link) -> Link {
+    Rc::new(Node {
+        metrics: left.metrics.add(right.metrics),
+        height: left.height.max(right.height) + 1,
+        kind: Kind::Branch(left, right),
+    })
+}
+
+fn children(node: &Link) -> (Link, Link) {
+    match &node.kind {
+        Kind::Branch(left, right) => (left.clone(), right.clone()),
+        Kind::Leaf(_) => unreachable!("only branches are taller than their neighbours"),
+    }
+}
+
+// Builds a branch from two trees whose heights differ by at most 2, rotating to bring the
+// difference back to at most 1.
+fn balance(left: Link, right: Link) -> Link {
+    if left.height > right.height + 1 {
+        let (outer, inner) = children(&left);
+        if outer.height >= inner.height {
+            branch(outer, branch(inner, right))
+        } else {
+            let (inner_left, inner_right) = children(&inner);
+            branch(branch(outer, inner_left), branch(inner_right, right))
+        }
+    } else if right.height > left.height + 1 {
+        let (inner, outer) = children(&right);
+        if outer.height >= inner.height {
+            branch(branch(left, inner), outer)
+        } else {
+            let (inner_left, inner_right) = children(&inner);
+            branch(branch(left, inner_left), branch(inner_right, outer))
+        }
+    } else {
+        branch(left, right)
+    }
+}
+
+// Puts two trees one after the other. The shorter one is hung off the side of the taller
+// one at a matching height, so only that side of the taller tree is rebuilt.
+fn join(left: Link, right: Link) -> Link {
+    if left.metrics.bytes == 0 {
+        return right;
+    }
+    if right.metrics.bytes == 0 {
+        return left;
+    }
+    if let (Kind::Leaf(a), Kind::Leaf(b)) = (&left.kind, &right.kind) {
+        if a.len() + b.len() <= MAX_CHUNK {
+            return leaf(format!("{a}{b}"));
+        }
+    }
+    if left.height > right.height + 1 {
+        let (outer, inner) = children(&left);
+        balance(outer, join(inner, right))
+    } else if right.height > left.height + 1 {
+        let (inner, outer) = children(&right);
+        balance(join(left, inner), outer)
+    } else {
+        branch(left, right)
+    }
+}
+
+fn byte_offset(text: &str, chars: usize) -> usize {
+    text.char_indices()
+        .nth(chars)
+        .map_or(text.len(), |(i, _)| i)
+}
+
+// Cuts a tree in two before char `at`.
+fn split(node: &Link, at: usize) -> (Link, Link) {
+    if at == 0 {
+        return (leaf(String::new()), node.clone());
+    }
+    if at >= node.metrics.chars {
+        return (node.clone(), leaf(String::new()));
+    }
+    match &node.kind {
+        Kind::Leaf(text) => {
+            let (a, b) = text.split_at(byte_offset(text, at));
+            (leaf(a.to_string()), leaf(b.to_string()))
+        }
+        Kind::Branch(left, right) => {
+            if at <= left.metrics.chars {
+                let (a, b) = split(left, at);
+                (a, join(b, right.clone()))
+            } else {
+                let (a, b) = split(right, at - left.metrics.chars);
+                (join(left.clone(), a), b)
+            }
+        }
+    }
+}
+
+// A balanced tree over the chunks, built by halving.
+fn build(chunks: &[String]) -> Link {
+    match chunks {
+        [] => leaf(String::new()),
+        [chunk] => leaf(chunk.clone()),
+        _ => {
+            let (left, right) = chunks.split_at(chunks.len() / 2);
+            branch(build(left), build(right))
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Rope {
+    root: Link,
+}
+
+impl Rope {
+    pub fn new() -> Rope {
+        Rope {
+            root: leaf(String::new()),
+        }
+    }
+
+    pub fn len_bytes(&self) -> usize {
+        self.root.metrics.bytes
+    }
+
+    pub fn len_chars(&self) -> usize {
+        self.root.metrics.chars
+    }
+
+    // Like `str::lines`, but counting the empty line after a final newline.
+    pub fn len_lines(&self) -> usize {
+        self.root.metrics.newlines + 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len_bytes() == 0
+    }
+
+    fn check_index(&self, at: usize) {
+        if at > self.len_chars() {
+            panic!(
+                "char index {at} is out of bounds of a rope of {} chars",
+                self.len_chars()
+            );
+        }
+    }
+
+    fn check_range(&self, range: &Range<usize>) {
+        self.check_index(range.end);
+        if range.start > range.end {
+            panic!("range starts at {} but ends at {}", range.start, range.end);
+        }
+    }
+
+    // Inserts `text` before char `at`. Panics if `at` is past the end, like `String::insert_str`.
+    pub fn insert(&mut self, at: usize, text: &str) {
+        self.check_index(at);
+        let (before, after) = split(&self.root, at);
+        let middle = Rope::from(text).root;
+        self.root = join(join(before, middle), after);
+    }
+
+    pub fn push_str(&mut self, text: &str) {
+        self.insert(self.len_chars(), text);
+    }
+
+    // Removes the chars in `range`.
+    pub fn remove(&mut self, range: Range<usize>) {
+        self.check_range(&range);
+        let (before, rest) = split(&self.root, range.start);
+        let (_, after) = split(&rest, range.end - range.start);
+        self.root = join(before, after);
+    }
+
+    // The chars in `range` as a rope of their own, which shares its chunks with this one.
+    pub fn slice(&self, range: Range<usize>) -> Rope {
+        self.check_range(&range);
+        let (_, rest) = split(&self.root, range.start);
+        let (middle, _) = split(&rest, range.end - range.start);
+        Rope { root: middle }
+    }
+
+    pub fn char(&self, at: usize) -> Option<char> {
+        if at >= self.len_chars() {
+            return None;
+        }
+        let mut node = &self.root;
+        let mut at = at;
+        loop {
+            match &node.kind {
+                Kind::Leaf(text) => return text.chars().nth(at),
+                Kind::Branch(left, right) => {
+                    if at < left.metrics.chars {
+                        node = left;
+                    } else {
+                        at -= left.metrics.chars;
+                        node = right;
+                    }
+                }
+            }
+        }
+    }
+
+    // The line that char `at` is on, counting from 0.
+    pub fn char_to_line(&self, at: usize) -> usize {
+        self.check_index(at);
+        let mut node = &self.root;
+        let (mut at, mut line) = (at, 0);
+        loop {
+            match &node.kind {
+                Kind::Leaf(text) => {
+                    return line + text.chars().take(at).filter(|&c| c == '\n').count();
+                }
+                Kind::Branch(left, right) => {
+                    if at < left.metrics.chars {
+                        node = left;
+                    } else {
+                        at -= left.metrics.chars;
+                        line += left.metrics.newlines;
+                        node = right;
+                    }
+                }
+            }
+        }
+    }
+
+    // The char where line `line` starts, or `None` if there are not that many lines.
+    pub fn line_to_char(&self, line: usize) -> Option<usize> {
+        if line >= self.len_lines() {
+            return None;
+        }
+        if line == 0 {
+            return Some(0);
+        }
+        // Look for the newline that ends line `line - 1`.
+        let mut node = &self.root;
+        let (mut newlines, mut start) = (line, 0);
+        loop {
+            match &node.kind {
+                Kind::Leaf(text) => {
+                    let (offset, _) = text
+                        .chars()
+                        .enumerate()
+                        .filter(|&(_, c)| c == '\n')
+                        .nth(newlines - 1)?;
+                    return Some(start + offset + 1);
+                }
+                Kind::Branch(left, right) => {
+                    if newlines <= left.metrics.newlines {
+                        node = left;
+                    } else {
+                        newlines -= left.metrics.newlines;
+                        start += left.metrics.chars;
+                        node = right;
+                    }
+                }
+            }
+        }
+    }
+
+    // Line `line` with its newline, if it has one.
+    pub fn line(&self, line: usize) -> Option<Rope> {
+        let start = self.line_to_char(line)?;
+        let end = self.line_to_char(line + 1).unwrap_or(self.len_chars());
+        Some(self.slice(start..end))
+    }
+
+    // The text in order, one chunk at a time.
+    pub fn chunks(&self) -> Chunks<'_> {
+        Chunks {
+            stack: vec![&self.root],
+        }
+    }
+}
+
+impl Default for Rope {
+    fn default() -> Rope {
+        Rope::new()
+    }
+}
+
+pub struct Chunks<'a> {
+    stack: Vec<&'a Link>,
+}
+
+impl<'a> Iterator for Chunks<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        while let Some(node) = self.stack.pop() {
+            match &node.kind {
+                Kind::Leaf(text) if text.is_empty() => {}
+                Kind::Leaf(text) => return Some(text),
+                Kind::Branch(left, right) => {
+                    self.stack.push(right);
+                    self.stack.push(left);
+                }
+            }
+        }
+        None
+    }
+}
+
+impl From<&str> for Rope {
+    fn from(text: &str) -> Rope {
+        let mut chunks = Vec::new();
+        let mut rest = text;
+        while !rest.is_empty() {
+            let mut end = rest.len().min(MAX_CHUNK);
+            while !rest.is_char_boundary(end) {
+                end -= 1;
+            }
+            let (chunk, after) = rest.split_at(end);
+            chunks.push(chunk.to_string());
+            rest = after;
+        }
+        Rope {
+            root: build(&chunks),
+        }
+    }
+}
+
+impl From<String> for Rope {
+    fn from(text: String) -> Rope {
+        Rope::from(text.as_str())
+    }
+}
+
+impl From<&Rope> for String {
+    fn from(rope: &Rope) -> String {
+        let mut text = String::with_capacity(rope.len_bytes());
+        for chunk in rope.chunks() {
+            text.push_str(chunk);
+        }
+        text
+    }
+}
+
+impl fmt::Display for Rope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for chunk in self.chunks() {
+            f.write_str(chunk)?;
+        }
+        Ok(())
+    }
+}
+
+impl PartialEq<str> for Rope {
+    fn eq(&self, other: &str) -> bool {
+        let mut rest = other.as_bytes();
+        for chunk in self.chunks() {
+            match rest.strip_prefix(chunk.as_bytes()) {
+                Some(after) => rest = after,
+                None => return false,
+            }
+        }
+        rest.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    // Checks the stored sizes and the balance of every node.
+    fn check(node: &Link) {
+        if let Kind::Branch(left, right) = &node.kind {
+            check(left);
+            check(right);
+            assert_eq!(node.metrics, left.metrics.add(right.metrics));
+            assert!(left.height.abs_diff(right.height) <= 1, "unbalanced node");
+        }
+    }
+
+    #[test]
+    fn edits_match_string() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let words = [
+            "héllo",
+            " ",
+            "你好\n",
+            "wörld",
+            "🦀",
+            "\n",
+            &"x".repeat(3000),
+        ];
+        let mut rope = Rope::new();
+        let mut expected: Vec<char> = Vec::new();
+
+        for _ in 0..500 {
+            let at = rng.gen_range(0..=expected.len());
+            if rng.gen_bool(0.7) || expected.is_empty() {
+                let word = words[rng.gen_range(0..words.len())];
+                rope.insert(at, word);
+                expected.splice(at..at, word.chars());
+            } else {
+                let end = rng.gen_range(at..=expected.len().min(at + 2000));
+                rope.remove(at..end);
+                expected.drain(at..end);
+            }
+            check(&rope.root);
+        }
+        let expected: String = expected.into_iter().collect();
+        assert_eq!(String::from(&rope), expected);
+        assert!(rope == *expected.as_str());
+        assert_eq!(rope.len_chars(), expected.chars().count());
+        assert_eq!(rope.len_bytes(), expected.len());
+        assert!(rope.root.height < 20, "height {}", rope.root.height);
+    }
+
+    #[test]
+    fn slices_share_and_do_not_change_the_original() {
+        let text = "a🦀c".repeat(1000);
+        let rope = Rope::from(text.as_str());
+        let piece = rope.slice(1..2000);
+        let expected: String = text.chars().skip(1).take(1999).collect();
+        assert_eq!(piece.to_string(), expected);
+        assert_eq!(rope.to_string(), text);
+        assert_eq!(rope.char(1), Some('🦀'));
+        assert_eq!(rope.char(2999), Some('c'));
+        assert_eq!(rope.char(3000), None);
+    }
+
+    #[test]
+    fn line_indexing() {
+        let mut rope = Rope::from("first\nsecond\n");
+        rope.insert(0, &"zero\n".repeat(500));
+        assert_eq!(rope.len_lines(), 503);
+        assert_eq!(rope.line_to_char(500), Some(2500));
+        assert_eq!(rope.line(500).unwrap().to_string(), "first\n");
+        assert_eq!(rope.line(501).unwrap().to_string(), "second\n");
+        assert_eq!(rope.line(502).unwrap().to_string(), "");
+        assert!(rope.line(503).is_none());
+        assert_eq!(rope.char_to_line(2500), 500);
+        assert_eq!(rope.char_to_line(2499), 499);
+        assert_eq!(rope.char_to_line(rope.len_chars()), 502);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn inserting_past_the_end_panics() {
+        Rope::from("abc").insert(4, "d");
+    }
+}
@@ -0,0 +1,92 @@
+// Times the same edits on a `String` and on a `Rope`, for documents of growing size:
+// `cargo run --release -- rope-bench`. Small documents fit in the cache and
+// `String::insert_str` only has to move a few bytes, so it wins there; once every insert
+// moves megabytes, the rope's O(log n) edits pull ahead.
+use super::Rope;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::time::{Duration, Instant};
+
+const INSERTS: usize = 2_000;
+const SIZES: [usize; 5] = [1_000, 10_000, 100_000, 1_000_000, 10_000_000];
+
+#[derive(Debug, Clone, Copy)]
+pub struct Timing {
+    pub size: usize,
+    pub string: Duration,
+    pub rope: Duration,
+}
+
+// A plain-ASCII document, so char and byte positions are the same for both types.
+fn document(size: usize) -> String {
+    "The quick brown fox jumps over the lazy dog.\n"
+        .chars()
+        .cycle()
+        .take(size)
+        .collect()
+}
+
+// Inserts a word at `inserts` random places in a document of `size` bytes, first in a
+// `String` and then in a `Rope`, and checks that both end up with the same text.
+pub fn compare(size: usize, inserts: usize) -> Timing {
+    let text = document(size);
+    let mut rng = StdRng::seed_from_u64(size as u64);
+    let positions: Vec<usize> = (0..inserts)
+        .map(|i| rng.gen_range(0..=size + i * 5))
+        .collect();
+
+    let mut string = text.clone();
+    let start = Instant::now();
+    for &at in &positions {
+        string.insert_str(at, "hello");
+    }
+    let string_time = start.elapsed();
+
+    let mut rope = Rope::from(text);
+    let start = Instant::now();
+    for &at in &positions {
+        rope.insert(at, "hello");
+    }
+    let rope_time = start.elapsed();
+
+    assert!(rope == *string, "the rope and the string disagree");
+    Timing {
+        size,
+        string: string_time,
+        rope: rope_time,
+    }
+}
+
+pub fn run() {
+    println!("{INSERTS} inserts of \"hello\" at random places:");
+    println!("{:>12}  {:>12}  {:>12}  faster", "bytes", "String", "Rope");
+    for size in SIZES {
+        let timing = compare(size, INSERTS);
+        let winner = if timing.rope < timing.string {
+            format!(
+                "Rope, {:.1}x",
+                timing.string.as_secs_f64() / timing.rope.as_secs_f64()
+            )
+        } else {
+            format!(
+                "String, {:.1}x",
+                timing.rope.as_secs_f64() / timing.string.as_secs_f64()
+            )
+        };
+        println!(
+            "{:>12}  {:>12.3?}  {:>12.3?}  {winner}",
+            timing.size, timing.string, timing.rope
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn both_types_agree() {
+        let timing = compare(5_000, 200);
+        assert_eq!(timing.size, 5_000);
+    }
+}
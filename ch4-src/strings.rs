@@ -0,0 +1,200 @@
+// Two alternatives to `String` that allocate less.
+//
+// `SmallString` keeps text of up to `INLINE_CAPACITY` bytes inside the value itself, the way
+// an `i32` lives on the stack, and only moves to the heap once it grows past that. Creating,
+// moving and dropping a short one never touches the allocator.
+//
+// `CowString` ("copy on write") shares one heap copy of its text between all its clones, so
+// cloning it is as cheap as copying a pointer. The text is only copied when a clone that
+// shares it is changed.
+use std::fmt;
+use std::ops::Deref;
+use std::rc::Rc;
+
+// 22 bytes of text and a length byte keep `SmallString` as small as a `String` plus a tag.
+pub const INLINE_CAPACITY: usize = 22;
+
+#[derive(Clone)]
+pub enum SmallString {
+    Inline {
+        len: u8,
+        bytes: [u8; INLINE_CAPACITY],
+    },
+    Heap(String),
+}
+
+impl SmallString {
+    pub fn new() -> SmallString {
+        SmallString::Inline {
+            len: 0,
+            bytes: [0; INLINE_CAPACITY],
+        }
+    }
+
+    pub fn is_inline(&self) -> bool {
+        matches!(self, SmallString::Inline { .. })
+    }
+
+    // Appends `text`, moving everything to the heap if it no longer fits inline.
+    pub fn push_str(&mut self, text: &str) {
+        match self {
+            SmallString::Inline { len, bytes } if *len as usize + text.len() <= INLINE_CAPACITY => {
+                let start = *len as usize;
+                bytes[start..start + text.len()].copy_from_slice(text.as_bytes());
+                *len += text.len() as u8;
+            }
+            SmallString::Inline { .. } => {
+                let mut heap = String::with_capacity(self.len() + text.len());
+                heap.push_str(self);
+                heap.push_str(text);
+                *self = SmallString::Heap(heap);
+            }
+            SmallString::Heap(heap) => heap.push_str(text),
+        }
+    }
+}
+
+impl Default for SmallString {
+    fn default() -> SmallString {
+        SmallString::new()
+    }
+}
+
+impl Deref for SmallString {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        match self {
+            // Only whole `&str`s are ever copied in, so the bytes are always valid UTF-8.
+            SmallString::Inline { len, bytes } => {
+                std::str::from_utf8(&bytes[..*len as usize]).expect("inline text is valid UTF-8")
+            }
+            SmallString::Heap(heap) => heap,
+        }
+    }
+}
+
+impl From<&str> for SmallString {
+    fn from(text: &str) -> SmallString {
+        let mut small = SmallString::new();
+        small.push_str(text);
+        small
+    }
+}
+
+impl fmt::Display for SmallString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(self)
+    }
+}
+
+impl fmt::Debug for SmallString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl PartialEq for SmallString {
+    fn eq(&self, other: &SmallString) -> bool {
+        **self == **other
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub struct CowString {
+    text: Rc<String>,
+}
+
+impl CowString {
+    // Whether another clone shares this text, so that changing it would copy it first.
+    pub fn is_shared(&self) -> bool {
+        Rc::strong_count(&self.text) > 1
+    }
+
+    // Appends `text`. A shared string is copied first, so the other clones keep their text.
+    pub fn push_str(&mut self, text: &str) {
+        Rc::make_mut(&mut self.text).push_str(text);
+    }
+}
+
+impl Deref for CowString {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.text
+    }
+}
+
+impl From<&str> for CowString {
+    fn from(text: &str) -> CowString {
+        CowString {
+            text: Rc::new(text.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for CowString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(self)
+    }
+}
+
+impl fmt::Debug for CowString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tracking::track;
+
+    #[test]
+    fn short_strings_stay_off_the_heap() {
+        let (small, stats) = track(|| SmallString::from("你好, world"));
+        assert!(stats.is_empty(), "{stats}");
+        assert!(small.is_inline());
+        assert_eq!(&*small, "你好, world");
+        assert_eq!(small.len(), 13);
+
+        let ((), stats) = track(|| drop(small.clone()));
+        assert!(stats.is_empty(), "{stats}");
+    }
+
+    #[test]
+    fn long_strings_spill_to_the_heap() {
+        let mut small = SmallString::from("x".repeat(INLINE_CAPACITY).as_str());
+        assert!(small.is_inline());
+        let ((), stats) = track(|| small.push_str("y"));
+        assert_eq!(stats.allocations, 1);
+        assert!(!small.is_inline());
+        assert_eq!(small.len(), INLINE_CAPACITY + 1);
+        assert!(small.ends_with("xy"));
+    }
+
+    #[test]
+    fn clones_share_until_one_changes() {
+        let original = CowString::from("hello");
+        let (mut copy, stats) = track(|| original.clone());
+        assert!(stats.is_empty(), "{stats}");
+        assert!(original.is_shared());
+
+        let ((), stats) = track(|| copy.push_str(", world"));
+        assert!(stats.allocations >= 2, "{stats}");
+        assert_eq!(&*original, "hello");
+        assert_eq!(&*copy, "hello, world");
+        assert!(!original.is_shared());
+
+        // With nobody else sharing it, the text is changed in place.
+        let ((), stats) = track(|| copy.push_str("!"));
+        assert_eq!(stats.allocations, 0, "{stats}");
+    }
+
+    #[test]
+    fn display_and_debug_show_the_text() {
+        assert_eq!(format!("{:>7}|", SmallString::from("hi")), "     hi|");
+        assert_eq!(format!("{:?}", CowString::from("hi")), "\"hi\"");
+        assert_eq!(SmallString::default(), SmallString::from(""));
+    }
+}
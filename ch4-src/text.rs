@@ -0,0 +1,237 @@
+// Text helpers built on string slices: each one hands back a `&str` that borrows from the
+// text it was given, like the chapter's `first_word`, instead of copying into a new `String`.
+//
+// Slicing with `&s[start..end]` counts bytes, and panics when a range ends inside a
+// multi-byte character: `&"你好"[0..1]` cuts the three bytes of `你`. The functions here
+// count characters or graphemes instead, and return `None` for a range that does not fit.
+
+// Words are the runs of text between whitespace, so "hello,  world" has the words
+// "hello," and "world". It is a double-ended iterator, so the last word comes from the back
+// without going through the rest.
+#[derive(Debug, Clone)]
+pub struct SplitWords<'a> {
+    rest: &'a str,
+}
+
+pub fn split_words(s: &str) -> SplitWords<'_> {
+    SplitWords { rest: s }
+}
+
+impl<'a> Iterator for SplitWords<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        let trimmed = self.rest.trim_start();
+        if trimmed.is_empty() {
+            self.rest = trimmed;
+            return None;
+        }
+        let end = trimmed.find(char::is_whitespace).unwrap_or(trimmed.len());
+        let (word, rest) = trimmed.split_at(end);
+        self.rest = rest;
+        Some(word)
+    }
+}
+
+impl<'a> DoubleEndedIterator for SplitWords<'a> {
+    fn next_back(&mut self) -> Option<&'a str> {
+        let trimmed = self.rest.trim_end();
+        if trimmed.is_empty() {
+            self.rest = trimmed;
+            return None;
+        }
+        // The whitespace before the word can be several bytes long, like the
+        // ideographic space, so the word starts after all of it.
+        let start = trimmed
+            .char_indices()
+            .rev()
+            .find(|(_, c)| c.is_whitespace())
+            .map_or(0, |(i, c)| i + c.len_utf8());
+        let (rest, word) = trimmed.split_at(start);
+        self.rest = rest;
+        Some(word)
+    }
+}
+
+// The first word, or "" if there are none. The book's version stops at the first space
+// byte; this one skips leading whitespace and knows about every kind of space.
+pub fn first_word(s: &str) -> &str {
+    split_words(s).next().unwrap_or("")
+}
+
+pub fn last_word(s: &str) -> &str {
+    split_words(s).next_back().unwrap_or("")
+}
+
+// The word at `index`, counting from 0.
+pub fn nth_word(s: &str, index: usize) -> Option<&str> {
+    split_words(s).nth(index)
+}
+
+// Turns the start and end of a range, counted in some unit, into byte offsets. `starts`
+// yields the byte offset where each unit begins.
+fn byte_range(
+    s: &str,
+    starts: impl Iterator<Item = usize>,
+    start: usize,
+    end: usize,
+) -> Option<&str> {
+    if start > end {
+        return None;
+    }
+    let mut offsets = starts.chain(std::iter::once(s.len()));
+    let from = offsets.nth(start)?;
+    let to = if end == start {
+        from
+    } else {
+        offsets.nth(end - start - 1)?
+    };
+    s.get(from..to)
+}
+
+// The characters from `start` up to but not including `end`, counting `char`s: one
+// `char` is one Unicode scalar value, such as `你` or `é`.
+pub fn char_slice(s: &str, start: usize, end: usize) -> Option<&str> {
+    byte_range(s, s.char_indices().map(|(i, _)| i), start, end)
+}
+
+// Like `char_slice`, but counting graphemes: what a reader sees as one character, even
+// when it is made of several `char`s, such as `e` followed by a combining accent.
+pub fn grapheme_slice(s: &str, start: usize, end: usize) -> Option<&str> {
+    let starts = graphemes(s).scan(0, |offset, grapheme| {
+        let start = *offset;
+        *offset += grapheme.len();
+        Some(start)
+    });
+    byte_range(s, starts, start, end)
+}
+
+// Splits text into graphemes. This covers the common cases of the Unicode rules (UAX #29)
+// without their full tables: combining marks, variation selectors, emoji skin tones,
+// emoji joined with a zero-width joiner, pairs of flag letters and "\r\n".
+#[derive(Debug, Clone)]
+pub struct Graphemes<'a> {
+    rest: &'a str,
+}
+
+pub fn graphemes(s: &str) -> Graphemes<'_> {
+    Graphemes { rest: s }
+}
+
+const ZERO_WIDTH_JOINER: char = '\u{200D}';
+
+// Characters that attach to the one before them.
+fn is_extend(c: char) -> bool {
+    matches!(c as u32,
+        0x0300..=0x036F // combining diacritical marks
+        | 0x1AB0..=0x1AFF
+        | 0x1DC0..=0x1DFF
+        | 0x20D0..=0x20FF // combining marks for symbols
+        | 0xFE00..=0xFE0F // variation selectors
+        | 0xFE20..=0xFE2F // combining half marks
+        | 0x1F3FB..=0x1F3FF // emoji skin tones
+        | 0xE0020..=0xE007F // emoji tag characters
+    )
+}
+
+// Two of these make a flag: 🇫 🇷 is 🇫🇷.
+fn is_regional_indicator(c: char) -> bool {
+    matches!(c as u32, 0x1F1E6..=0x1F1FF)
+}
+
+impl<'a> Iterator for Graphemes<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        let mut chars = self.rest.char_indices();
+        let (_, first) = chars.next()?;
+        let mut end = first.len_utf8();
+        let mut previous = first;
+        let mut flag_letters = usize::from(is_regional_indicator(first));
+
+        for (i, c) in chars {
+            let joins = match (previous, c) {
+                ('\r', '\n') => true,
+                ('\r' | '\n', _) => false,
+                (_, c) if is_extend(c) || c == ZERO_WIDTH_JOINER => true,
+                (ZERO_WIDTH_JOINER, _) => true,
+                (p, c) => {
+                    is_regional_indicator(p) && is_regional_indicator(c) && flag_letters % 2 == 1
+                }
+            };
+            if !joins {
+                break;
+            }
+            if is_regional_indicator(c) {
+                flag_letters += 1;
+            }
+            end = i + c.len_utf8();
+            previous = c;
+        }
+
+        let (grapheme, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        Some(grapheme)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn words_borrow_from_the_text() {
+        let s = String::from("  hello  world\tand\u{3000}你好 ");
+        let words: Vec<&str> = split_words(&s).collect();
+        assert_eq!(words, ["hello", "world", "and", "你好"]);
+        assert_eq!(first_word(&s), "hello");
+        assert_eq!(last_word(&s), "你好");
+        assert_eq!(nth_word(&s, 2), Some("and"));
+        assert_eq!(nth_word(&s, 4), None);
+        // The slice points into `s` itself, not into a copy.
+        assert!(s
+            .as_bytes()
+            .as_ptr_range()
+            .contains(&first_word(&s).as_ptr()));
+    }
+
+    #[test]
+    fn words_from_both_ends() {
+        let mut words = split_words("one two three four");
+        assert_eq!(words.next(), Some("one"));
+        assert_eq!(words.next_back(), Some("four"));
+        assert_eq!(words.next_back(), Some("three"));
+        assert_eq!(words.next(), Some("two"));
+        assert_eq!(words.next(), None);
+        assert_eq!(words.next_back(), None);
+        assert_eq!(first_word(" \n "), "");
+        assert_eq!(last_word(""), "");
+    }
+
+    #[test]
+    fn char_slices_never_split_a_character() {
+        let s = "你好, world";
+        assert_eq!(char_slice(s, 0, 1), Some("你"));
+        assert_eq!(char_slice(s, 0, 2), Some("你好"));
+        assert_eq!(char_slice(s, 4, 9), Some("world"));
+        assert_eq!(char_slice(s, 9, 9), Some(""));
+        assert_eq!(char_slice(s, 4, 10), None);
+        assert_eq!(char_slice(s, 3, 2), None);
+        // The byte-counting version of the first line would panic.
+        assert!(s.get(0..1).is_none());
+    }
+
+    #[test]
+    fn graphemes_keep_marks_and_emoji_together() {
+        let accent = "e\u{301}"; // "é" as `e` and a combining acute accent
+        let family = "👨\u{200D}👩\u{200D}👧";
+        let text = format!("{accent}👍🏽🇫🇷🇩🇪{family}\r\nx");
+        let pieces: Vec<&str> = graphemes(&text).collect();
+        assert_eq!(pieces, [accent, "👍🏽", "🇫🇷", "🇩🇪", family, "\r\n", "x"]);
+        assert_eq!(grapheme_slice(&text, 0, 1), Some(accent));
+        assert_eq!(grapheme_slice(&text, 2, 4), Some("🇫🇷🇩🇪"));
+        assert_eq!(grapheme_slice(&text, 6, 7), Some("x"));
+        assert_eq!(grapheme_slice(&text, 6, 8), None);
+        assert_eq!(char_slice(&text, 0, 1), Some("e"));
+    }
+}
@@ -0,0 +1,290 @@
+// `Traced<T>` wraps a value and writes down what happens to it: where it was created, each
+// time it is handed to a new owner or cloned, and when it is dropped. A `Timeline` collects
+// those events for a stretch of code and prints them in order, which shows exactly when
+// values go out of scope and that a moved-from variable is never dropped.
+//
+// Rust moves are plain memory copies that no code can watch, so a move is only recorded
+// when it goes through `hand_off`. Drops are recorded by `Drop` itself, which cannot know
+// the line it runs at, so they are shown without a location unless the value is ended
+// with `drop_here` at the point where it would go out of scope.
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::ops::Deref;
+use std::panic::Location;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Kind {
+    Created { name: String },
+    Moved { from: String, to: String },
+    Cloned { from: String, to: String },
+    Dropped { name: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    // Every value gets its own id, which stays the same when it is handed off.
+    pub id: usize,
+    pub kind: Kind,
+    pub location: Option<&'static Location<'static>>,
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (verb, what) = match &self.kind {
+            Kind::Created { name } => ("created", name.clone()),
+            Kind::Moved { from, to } => ("moved", format!("{from} -> {to}")),
+            Kind::Cloned { from, to } => ("cloned", format!("{from} -> {to}")),
+            Kind::Dropped { name } => ("dropped", name.clone()),
+        };
+        let what = format!("{what} (#{})", self.id);
+        match self.location {
+            Some(location) => write!(f, "{verb:<8} {what:<28} at {location}"),
+            None => write!(f, "{verb:<8} {what}"),
+        }
+    }
+}
+
+thread_local! {
+    static LOG: RefCell<Vec<Event>> = const { RefCell::new(Vec::new()) };
+    // How many timelines are open; events are only kept while at least one is.
+    static OPEN: Cell<usize> = const { Cell::new(0) };
+    static NEXT_ID: Cell<usize> = const { Cell::new(1) };
+}
+
+fn record(id: usize, kind: Kind, location: Option<&'static Location<'static>>) {
+    if OPEN.with(Cell::get) > 0 {
+        LOG.with(|log| log.borrow_mut().push(Event { id, kind, location }));
+    }
+}
+
+fn next_id() -> usize {
+    NEXT_ID.with(|next| next.replace(next.get() + 1))
+}
+
+pub struct Traced<T> {
+    id: usize,
+    name: String,
+    // Only `None` after `hand_off` has taken the value out, so the old owner's drop
+    // does not count as dropping the value.
+    value: Option<T>,
+}
+
+impl<T> Traced<T> {
+    #[track_caller]
+    pub fn new(name: &str, value: T) -> Traced<T> {
+        let id = next_id();
+        let name = name.to_string();
+        record(
+            id,
+            Kind::Created { name: name.clone() },
+            Some(Location::caller()),
+        );
+        Traced {
+            id,
+            name,
+            value: Some(value),
+        }
+    }
+
+    // Moves the value to a new owner called `to`, like `let s2 = s1;`.
+    #[track_caller]
+    pub fn hand_off(mut self, to: &str) -> Traced<T> {
+        let kind = Kind::Moved {
+            from: self.name.clone(),
+            to: to.to_string(),
+        };
+        record(self.id, kind, Some(Location::caller()));
+        Traced {
+            id: self.id,
+            name: to.to_string(),
+            value: self.value.take(),
+        }
+    }
+
+    // Drops the value now, like `drop(value)`, and records the line this is called from.
+    #[track_caller]
+    pub fn drop_here(mut self) {
+        let name = self.name.clone();
+        record(self.id, Kind::Dropped { name }, Some(Location::caller()));
+        self.value = None;
+    }
+
+    pub fn id(&self) -> usize {
+        self.id
+    }
+}
+
+impl<T: Clone> Traced<T> {
+    // A deep copy, like `let s3 = s2.clone();`, which becomes a value of its own.
+    #[track_caller]
+    pub fn clone_as(&self, to: &str) -> Traced<T> {
+        let id = next_id();
+        let kind = Kind::Cloned {
+            from: self.name.clone(),
+            to: to.to_string(),
+        };
+        record(id, kind, Some(Location::caller()));
+        Traced {
+            id,
+            name: to.to_string(),
+            value: self.value.clone(),
+        }
+    }
+}
+
+impl<T> Deref for Traced<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value.as_ref().unwrap()
+    }
+}
+
+impl<T> Drop for Traced<T> {
+    fn drop(&mut self) {
+        if self.value.is_some() {
+            let name = self.name.clone();
+            record(self.id, Kind::Dropped { name }, None);
+        }
+    }
+}
+
+// Collects the events of every `Traced` value on this thread from `start` until it is dropped.
+pub struct Timeline {
+    start: usize,
+}
+
+impl Timeline {
+    pub fn start() -> Timeline {
+        OPEN.with(|open| open.set(open.get() + 1));
+        Timeline {
+            start: LOG.with(|log| log.borrow().len()),
+        }
+    }
+
+    pub fn events(&self) -> Vec<Event> {
+        LOG.with(|log| log.borrow()[self.start..].to_vec())
+    }
+
+    // How many times the value with this id was dropped; a correct program says 1 for
+    // everything that was created and has gone out of scope.
+    pub fn drops_of(&self, id: usize) -> usize {
+        self.events()
+            .iter()
+            .filter(|event| event.id == id && matches!(event.kind, Kind::Dropped { .. }))
+            .count()
+    }
+
+    pub fn render(&self) -> String {
+        self.events()
+            .iter()
+            .enumerate()
+            .map(|(i, event)| format!("{:>3}. {event}\n", i + 1))
+            .collect()
+    }
+}
+
+impl Drop for Timeline {
+    fn drop(&mut self) {
+        let open = OPEN.with(|open| {
+            open.set(open.get() - 1);
+            open.get()
+        });
+        if open == 0 {
+            LOG.with(|log| log.borrow_mut().clear());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(timeline: &Timeline) -> Vec<Kind> {
+        timeline
+            .events()
+            .into_iter()
+            .map(|event| event.kind)
+            .collect()
+    }
+
+    fn name(s: &str) -> String {
+        s.to_string()
+    }
+
+    #[test]
+    fn a_moved_value_is_dropped_once() {
+        let timeline = Timeline::start();
+        let id;
+        {
+            let s1 = Traced::new("s1", String::from("hello"));
+            id = s1.id();
+            let s2 = s1.hand_off("s2");
+            assert_eq!(*s2, "hello");
+        }
+        assert_eq!(timeline.drops_of(id), 1);
+        assert_eq!(
+            kinds(&timeline),
+            [
+                Kind::Created { name: name("s1") },
+                Kind::Moved {
+                    from: name("s1"),
+                    to: name("s2")
+                },
+                Kind::Dropped { name: name("s2") },
+            ]
+        );
+    }
+
+    #[test]
+    fn values_drop_in_reverse_order_of_creation() {
+        let timeline = Timeline::start();
+        {
+            let a = Traced::new("a", 1);
+            let _b = a.clone_as("b");
+            let _c = Traced::new("c", 3);
+        }
+        let dropped: Vec<Kind> = kinds(&timeline).into_iter().skip(3).collect();
+        assert_eq!(
+            dropped,
+            [
+                Kind::Dropped { name: name("c") },
+                Kind::Dropped { name: name("b") },
+                Kind::Dropped { name: name("a") },
+            ]
+        );
+    }
+
+    #[test]
+    fn events_carry_their_source_location() {
+        let timeline = Timeline::start();
+        let line = line!() + 1;
+        let value = Traced::new("value", ());
+        let events = timeline.events();
+        let location = events[0].location.unwrap();
+        assert!(location.file().ends_with("traced.rs"));
+        assert_eq!(location.line(), line);
+        drop(value);
+        assert!(timeline.render().contains("  2. dropped  value (#"));
+    }
+
+    #[test]
+    fn drop_here_records_where_the_value_ends() {
+        let timeline = Timeline::start();
+        let value = Traced::new("value", ());
+        let id = value.id();
+        let line = line!() + 1;
+        value.drop_here();
+        let events = timeline.events();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1].location.unwrap().line(), line);
+        assert_eq!(timeline.drops_of(id), 1);
+    }
+
+    #[test]
+    fn nothing_is_kept_without_a_timeline() {
+        drop(Traced::new("unseen", 0));
+        let timeline = Timeline::start();
+        assert!(timeline.events().is_empty());
+    }
+}
@@ -0,0 +1,276 @@
+// A global allocator that counts what the program asks of the heap, so the allocations and
+// frees that `main` describes in comments can be watched as they happen. It hands the real
+// work to the system allocator and only keeps score.
+//
+// Every count is kept twice: once for the whole process, and once for the thread that made
+// the request. A `Tracker` reads the per-thread numbers, so tests running side by side on
+// other threads do not show up in each other's results.
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub allocations: usize,
+    pub reallocations: usize,
+    pub deallocations: usize,
+    pub bytes_allocated: usize,
+    pub bytes_freed: usize,
+}
+
+impl Stats {
+    // Bytes allocated but not yet freed. Negative when memory allocated before the
+    // tracking started was freed during it.
+    pub fn bytes_live(&self) -> isize {
+        self.bytes_allocated as isize - self.bytes_freed as isize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Stats::default()
+    }
+
+    fn since(self, earlier: Stats) -> Stats {
+        Stats {
+            allocations: self.allocations - earlier.allocations,
+            reallocations: self.reallocations - earlier.reallocations,
+            deallocations: self.deallocations - earlier.deallocations,
+            bytes_allocated: self.bytes_allocated - earlier.bytes_allocated,
+            bytes_freed: self.bytes_freed - earlier.bytes_freed,
+        }
+    }
+}
+
+fn plural(count: usize, word: &str) -> String {
+    if count == 1 {
+        format!("{count} {word}")
+    } else {
+        format!("{count} {word}s")
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "no heap activity");
+        }
+        let mut parts = Vec::new();
+        if self.allocations > 0 {
+            parts.push(plural(self.allocations, "allocation"));
+        }
+        if self.reallocations > 0 {
+            parts.push(plural(self.reallocations, "reallocation"));
+        }
+        if self.deallocations > 0 {
+            parts.push(plural(self.deallocations, "free"));
+        }
+        let mut bytes = Vec::new();
+        if self.bytes_allocated > 0 {
+            bytes.push(format!(
+                "{} allocated",
+                plural(self.bytes_allocated, "byte")
+            ));
+        }
+        if self.bytes_freed > 0 {
+            bytes.push(format!("{} freed", plural(self.bytes_freed, "byte")));
+        }
+        write!(f, "{} ({})", parts.join(", "), bytes.join(", "))
+    }
+}
+
+struct Totals {
+    allocations: AtomicUsize,
+    reallocations: AtomicUsize,
+    deallocations: AtomicUsize,
+    bytes_allocated: AtomicUsize,
+    bytes_freed: AtomicUsize,
+}
+
+static TOTALS: Totals = Totals {
+    allocations: AtomicUsize::new(0),
+    reallocations: AtomicUsize::new(0),
+    deallocations: AtomicUsize::new(0),
+    bytes_allocated: AtomicUsize::new(0),
+    bytes_freed: AtomicUsize::new(0),
+};
+
+thread_local! {
+    // A `const` thread local of a `Copy` type needs no setup and no destructor, so the
+    // allocator can use it without allocating, even while a thread is shutting down.
+    static THREAD: Cell<Stats> = const {
+        Cell::new(Stats {
+            allocations: 0,
+            reallocations: 0,
+            deallocations: 0,
+            bytes_allocated: 0,
+            bytes_freed: 0,
+        })
+    };
+}
+
+fn record(change: impl Fn(&mut Stats)) {
+    let _ = THREAD.try_with(|cell| {
+        let mut stats = cell.get();
+        change(&mut stats);
+        cell.set(stats);
+    });
+}
+
+// Everything counted so far, on every thread.
+pub fn totals() -> Stats {
+    Stats {
+        allocations: TOTALS.allocations.load(Ordering::Relaxed),
+        reallocations: TOTALS.reallocations.load(Ordering::Relaxed),
+        deallocations: TOTALS.deallocations.load(Ordering::Relaxed),
+        bytes_allocated: TOTALS.bytes_allocated.load(Ordering::Relaxed),
+        bytes_freed: TOTALS.bytes_freed.load(Ordering::Relaxed),
+    }
+}
+
+fn this_thread() -> Stats {
+    THREAD.with(Cell::get)
+}
+
+// Install it with `#[global_allocator]`; a program can only have one.
+pub struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc(layout) };
+        if !ptr.is_null() {
+            TOTALS.allocations.fetch_add(1, Ordering::Relaxed);
+            TOTALS
+                .bytes_allocated
+                .fetch_add(layout.size(), Ordering::Relaxed);
+            record(|stats| {
+                stats.allocations += 1;
+                stats.bytes_allocated += layout.size();
+            });
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) };
+        TOTALS.deallocations.fetch_add(1, Ordering::Relaxed);
+        TOTALS
+            .bytes_freed
+            .fetch_add(layout.size(), Ordering::Relaxed);
+        record(|stats| {
+            stats.deallocations += 1;
+            stats.bytes_freed += layout.size();
+        });
+    }
+
+    // Growing a `String` moves it to a bigger block: count that as the old size freed
+    // and the new size allocated.
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = unsafe { System.realloc(ptr, layout, new_size) };
+        if !new_ptr.is_null() {
+            TOTALS.reallocations.fetch_add(1, Ordering::Relaxed);
+            TOTALS
+                .bytes_allocated
+                .fetch_add(new_size, Ordering::Relaxed);
+            TOTALS
+                .bytes_freed
+                .fetch_add(layout.size(), Ordering::Relaxed);
+            record(|stats| {
+                stats.reallocations += 1;
+                stats.bytes_allocated += new_size;
+                stats.bytes_freed += layout.size();
+            });
+        }
+        new_ptr
+    }
+}
+
+// Watches the heap activity of the current thread from the moment it is started.
+pub struct Tracker {
+    start: Stats,
+}
+
+impl Tracker {
+    pub fn start() -> Tracker {
+        Tracker {
+            start: this_thread(),
+        }
+    }
+
+    pub fn stats(&self) -> Stats {
+        this_thread().since(self.start)
+    }
+
+    // Prints what happened since the last report and starts counting again. Printing can
+    // allocate too (the first print sets up stdout's buffer), so that is left out.
+    pub fn report(&mut self, what: &str) {
+        let stats = self.stats();
+        println!("  [heap] {what}: {stats}");
+        self.start = this_thread();
+    }
+}
+
+// Runs `f` and returns its result with the heap activity it caused on this thread, for
+// tests that check what a function allocates.
+#[cfg(test)]
+pub fn track<T>(f: impl FnOnce() -> T) -> (T, Stats) {
+    let tracker = Tracker::start();
+    let result = f();
+    (result, tracker.stats())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn string_from_allocates_and_drop_frees() {
+        let (s, stats) = track(|| String::from("hello"));
+        assert_eq!(stats.allocations, 1);
+        assert_eq!(stats.bytes_allocated, 5);
+
+        let ((), stats) = track(|| drop(s));
+        assert_eq!(stats.deallocations, 1);
+        assert_eq!(stats.bytes_freed, 5);
+        assert_eq!(stats.bytes_live(), -5);
+    }
+
+    #[test]
+    fn moves_and_copies_do_not_allocate() {
+        let s1 = String::from("hello");
+        let (s2, stats) = track(|| s1);
+        assert!(stats.is_empty(), "{stats}");
+        let (_, stats) = track(|| s2.len() + 5);
+        assert!(stats.is_empty(), "{stats}");
+    }
+
+    #[test]
+    fn growing_reallocates() {
+        let mut s = String::with_capacity(1);
+        let ((), stats) = track(|| s.push_str("hello, world!"));
+        assert_eq!(stats.reallocations, 1);
+        assert_eq!(stats.bytes_live(), s.capacity() as isize - 1);
+        assert!(totals().reallocations >= 1);
+    }
+
+    #[test]
+    fn describes_stats() {
+        let stats = Stats {
+            allocations: 1,
+            deallocations: 2,
+            bytes_allocated: 5,
+            bytes_freed: 10,
+            ..Stats::default()
+        };
+        assert_eq!(
+            stats.to_string(),
+            "1 allocation, 2 frees (5 bytes allocated, 10 bytes freed)"
+        );
+        let freed = Stats {
+            deallocations: 1,
+            bytes_freed: 5,
+            ..Stats::default()
+        };
+        assert_eq!(freed.to_string(), "1 free (5 bytes freed)");
+        assert_eq!(Stats::default().to_string(), "no heap activity");
+    }
+}
@@ -0,0 +1,481 @@
+// Rectangles that have a place as well as a size, for any number type: `Rect<u32>` for
+// pixels like the chapter's `Rectangle`, `Rect<i64>` for coordinates that can go negative,
+// `Rect<f64>` for measurements.
+//
+// Coordinates follow the screen: `x` grows to the right and `y` grows down, so `origin` is
+// the top-left corner. A rectangle covers the points from its left edge up to but not
+// including its right edge (and the same from top to bottom), so two rectangles that only
+// touch along an edge do not overlap.
+//
+// Every operation that does arithmetic comes in two forms. The `checked_` one returns `None`
+// when a result does not fit in `T` (or, for floats, is not a finite number), and the plain
+// one panics saying what overflowed. Unlike `+` and `*` on integers, which panic in debug
+// builds but wrap around in release builds, the plain forms panic in both. A rectangle can
+// only be made through `new` and the other methods here, which keep every edge within `T`,
+// so `right`, `bottom` and comparisons such as `contains_point` and `intersects` never panic.
+use std::cmp::Ordering;
+use std::fmt::Debug;
+use std::ops::{Add, Mul, Sub};
+
+// The number types a rectangle can be made of.
+pub trait Coord:
+    Copy + Debug + PartialOrd + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self>
+{
+    const ZERO: Self;
+    const TWO: Self;
+
+    fn checked_add(self, other: Self) -> Option<Self>;
+    fn checked_sub(self, other: Self) -> Option<Self>;
+    fn checked_mul(self, other: Self) -> Option<Self>;
+}
+
+macro_rules! int_coord {
+    ($($t:ty),*) => {
+        $(impl Coord for $t {
+            const ZERO: Self = 0;
+            const TWO: Self = 2;
+
+            fn checked_add(self, other: Self) -> Option<Self> {
+                <$t>::checked_add(self, other)
+            }
+            fn checked_sub(self, other: Self) -> Option<Self> {
+                <$t>::checked_sub(self, other)
+            }
+            fn checked_mul(self, other: Self) -> Option<Self> {
+                <$t>::checked_mul(self, other)
+            }
+        })*
+    };
+}
+
+// Floats do not wrap; they overflow to infinity, which is counted as not fitting.
+macro_rules! float_coord {
+    ($($t:ty),*) => {
+        $(impl Coord for $t {
+            const ZERO: Self = 0.0;
+            const TWO: Self = 2.0;
+
+            fn checked_add(self, other: Self) -> Option<Self> {
+                Some(self + other).filter(|sum| sum.is_finite())
+            }
+            fn checked_sub(self, other: Self) -> Option<Self> {
+                Some(self - other).filter(|difference| difference.is_finite())
+            }
+            fn checked_mul(self, other: Self) -> Option<Self> {
+                Some(self * other).filter(|product| product.is_finite())
+            }
+        })*
+    };
+}
+
+int_coord!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+float_coord!(f32, f64);
+
+fn expect<T>(value: Option<T>, what: &str) -> T {
+    match value {
+        Some(value) => value,
+        None => panic!("Rect {what} overflowed."),
+    }
+}
+
+fn min<T: Coord>(a: T, b: T) -> T {
+    if b < a {
+        b
+    } else {
+        a
+    }
+}
+
+fn max<T: Coord>(a: T, b: T) -> T {
+    if b > a {
+        b
+    } else {
+        a
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Point<T> {
+    x: T,
+    y: T,
+}
+
+impl<T: Copy> Point<T> {
+    pub fn new(x: T, y: T) -> Point<T> {
+        Point { x, y }
+    }
+
+    pub fn x(&self) -> T {
+        self.x
+    }
+
+    pub fn y(&self) -> T {
+        self.y
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect<T> {
+    origin: Point<T>,
+    width: T,
+    height: T,
+}
+
+impl<T: Coord> Rect<T> {
+    // Panics if `width` or `height` is negative (or NaN), or if the right or bottom edge
+    // is past the largest `T`.
+    pub fn new(x: T, y: T, width: T, height: T) -> Rect<T> {
+        if !(width >= T::ZERO && height >= T::ZERO) {
+            panic!("Rect width and height must not be negative, got {width:?} x {height:?}.");
+        }
+        expect(Rect::checked_new(x, y, width, height), "edge")
+    }
+
+    pub fn checked_new(x: T, y: T, width: T, height: T) -> Option<Rect<T>> {
+        if !(width >= T::ZERO && height >= T::ZERO) {
+            return None;
+        }
+        x.checked_add(width)?;
+        y.checked_add(height)?;
+        Some(Rect {
+            origin: Point { x, y },
+            width,
+            height,
+        })
+    }
+
+    // The rectangle with two opposite corners at `a` and `b`, in any order. For signed
+    // types the distance between the corners can be too big, as from `i32::MIN` to
+    // `i32::MAX`.
+    pub fn from_corners(a: Point<T>, b: Point<T>) -> Rect<T> {
+        expect(Rect::checked_from_corners(a, b), "size")
+    }
+
+    pub fn checked_from_corners(a: Point<T>, b: Point<T>) -> Option<Rect<T>> {
+        let (left, right) = (min(a.x, b.x), max(a.x, b.x));
+        let (top, bottom) = (min(a.y, b.y), max(a.y, b.y));
+        Rect::checked_new(
+            left,
+            top,
+            right.checked_sub(left)?,
+            bottom.checked_sub(top)?,
+        )
+    }
+
+    // The top-left corner.
+    pub fn origin(&self) -> Point<T> {
+        self.origin
+    }
+
+    pub fn width(&self) -> T {
+        self.width
+    }
+
+    pub fn height(&self) -> T {
+        self.height
+    }
+
+    pub fn left(&self) -> T {
+        self.origin.x
+    }
+
+    pub fn top(&self) -> T {
+        self.origin.y
+    }
+
+    // `new` has already checked that both edges fit, so these sums cannot overflow.
+    pub fn right(&self) -> T {
+        self.origin.x + self.width
+    }
+
+    pub fn bottom(&self) -> T {
+        self.origin.y + self.height
+    }
+
+    pub fn area(&self) -> T {
+        expect(self.checked_area(), "area")
+    }
+
+    pub fn checked_area(&self) -> Option<T> {
+        self.width.checked_mul(self.height)
+    }
+
+    pub fn perimeter(&self) -> T {
+        expect(self.checked_perimeter(), "perimeter")
+    }
+
+    pub fn checked_perimeter(&self) -> Option<T> {
+        self.width.checked_add(self.height)?.checked_mul(T::TWO)
+    }
+
+    // A rectangle with no width or no height covers no points at all.
+    pub fn is_empty(&self) -> bool {
+        self.width == T::ZERO || self.height == T::ZERO
+    }
+
+    pub fn contains_point(&self, point: Point<T>) -> bool {
+        self.left() <= point.x
+            && point.x < self.right()
+            && self.top() <= point.y
+            && point.y < self.bottom()
+    }
+
+    // Whether `other` fits entirely inside this rectangle; an empty one fits anywhere inside.
+    pub fn contains(&self, other: &Rect<T>) -> bool {
+        self.left() <= other.left()
+            && other.right() <= self.right()
+            && self.top() <= other.top()
+            && other.bottom() <= self.bottom()
+    }
+
+    // Whether the two rectangles share any points.
+    pub fn intersects(&self, other: &Rect<T>) -> bool {
+        self.intersection(other).is_some()
+    }
+
+    // The points both rectangles cover, or `None` if they do not overlap.
+    pub fn intersection(&self, other: &Rect<T>) -> Option<Rect<T>> {
+        let left = max(self.left(), other.left());
+        let top = max(self.top(), other.top());
+        let right = min(self.right(), other.right());
+        let bottom = min(self.bottom(), other.bottom());
+        // The overlap is never wider or taller than either rectangle, so this cannot overflow.
+        if left < right && top < bottom {
+            Some(Rect::new(left, top, right - left, bottom - top))
+        } else {
+            None
+        }
+    }
+
+    // The smallest rectangle covering both, also called their bounding box.
+    pub fn union(&self, other: &Rect<T>) -> Rect<T> {
+        expect(self.checked_union(other), "bounding box")
+    }
+
+    pub fn checked_union(&self, other: &Rect<T>) -> Option<Rect<T>> {
+        Rect::checked_from_corners(
+            Point {
+                x: min(self.left(), other.left()),
+                y: min(self.top(), other.top()),
+            },
+            Point {
+                x: max(self.right(), other.right()),
+                y: max(self.bottom(), other.bottom()),
+            },
+        )
+    }
+
+    // The same rectangle moved by `dx` to the right and `dy` down.
+    pub fn translate(&self, dx: T, dy: T) -> Rect<T> {
+        expect(self.checked_translate(dx, dy), "position")
+    }
+
+    pub fn checked_translate(&self, dx: T, dy: T) -> Option<Rect<T>> {
+        Rect::checked_new(
+            self.left().checked_add(dx)?,
+            self.top().checked_add(dy)?,
+            self.width,
+            self.height,
+        )
+    }
+
+    // Grows or shrinks the size by `factor`, keeping the top-left corner where it is.
+    // Panics if `factor` is negative.
+    pub fn scale(&self, factor: T) -> Rect<T> {
+        if matches!(factor.partial_cmp(&T::ZERO), None | Some(Ordering::Less)) {
+            panic!("Rect scale factor must not be negative, got {factor:?}.");
+        }
+        expect(self.checked_scale(factor), "size")
+    }
+
+    pub fn checked_scale(&self, factor: T) -> Option<Rect<T>> {
+        Rect::checked_new(
+            self.left(),
+            self.top(),
+            self.width.checked_mul(factor)?,
+            self.height.checked_mul(factor)?,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    const CASES: usize = 2_000;
+
+    fn random_rect(rng: &mut StdRng) -> Rect<i64> {
+        Rect::new(
+            rng.gen_range(-50..50),
+            rng.gen_range(-50..50),
+            rng.gen_range(0..40),
+            rng.gen_range(0..40),
+        )
+    }
+
+    fn random_point(rng: &mut StdRng) -> Point<i64> {
+        Point {
+            x: rng.gen_range(-60..100),
+            y: rng.gen_range(-60..100),
+        }
+    }
+
+    #[test]
+    fn measurements() {
+        let rect = Rect::new(10u32, 20, 30, 50);
+        assert_eq!(rect.origin(), Point::new(10, 20));
+        assert_eq!((rect.width(), rect.height()), (30, 50));
+        assert_eq!((rect.right(), rect.bottom()), (40, 70));
+        assert_eq!(rect.area(), 1500);
+        assert_eq!(rect.perimeter(), 160);
+        assert!(Rect::new(0.5, 0.5, 0.0, 3.0).is_empty());
+        assert_eq!(
+            Rect::from_corners(Point { x: 5, y: -1 }, Point { x: -3, y: 4 }),
+            Rect::new(-3, -1, 8, 5)
+        );
+    }
+
+    #[test]
+    fn edges_that_touch_do_not_overlap() {
+        let a = Rect::new(0, 0, 10, 10);
+        let b = Rect::new(10, 0, 10, 10);
+        assert!(!a.intersects(&b));
+        assert!(a.contains_point(Point { x: 0, y: 9 }));
+        assert!(!a.contains_point(Point { x: 10, y: 5 }));
+        assert_eq!(a.union(&b), Rect::new(0, 0, 20, 10));
+    }
+
+    #[test]
+    fn floats_work_too() {
+        let a = Rect::new(0.0, 0.0, 1.5, 2.0);
+        let b = a.translate(1.0, 1.0).scale(2.0);
+        assert_eq!(b, Rect::new(1.0, 1.0, 3.0, 4.0));
+        assert_eq!(a.intersection(&b), Some(Rect::new(1.0, 1.0, 0.5, 1.0)));
+    }
+
+    #[test]
+    #[should_panic(expected = "must not be negative")]
+    fn negative_sizes_are_rejected() {
+        Rect::new(0, 0, -1, 5);
+    }
+
+    #[test]
+    fn overflow_at_the_limits() {
+        let max = u32::MAX;
+        let whole = Rect::new(0, 0, max, max);
+        assert_eq!((whole.right(), whole.bottom()), (max, max));
+        assert!(whole.contains_point(Point {
+            x: max - 1,
+            y: max - 1
+        }));
+        assert_eq!(whole.checked_area(), None);
+        assert_eq!(whole.checked_perimeter(), None);
+        assert_eq!(Rect::new(0, 0, max, 1).checked_area(), Some(max));
+        assert_eq!(Rect::checked_new(1, 0, max, 1), None);
+        assert_eq!(whole.checked_translate(1, 0), None);
+        assert_eq!(whole.checked_scale(2), None);
+        assert_eq!(whole.checked_scale(1), Some(whole));
+
+        let corner = Rect::new(max - 1, max - 1, 1, 1);
+        assert_eq!(corner.intersection(&whole), Some(corner));
+        assert_eq!(corner.union(&Rect::new(0, 0, 1, 1)), whole);
+
+        // The distance from i32::MIN to i32::MAX does not fit in an i32.
+        let far_left = Rect::new(i32::MIN, 0, 1, 1);
+        let far_right = Rect::new(i32::MAX - 1, 0, 1, 1);
+        assert_eq!(far_left.checked_union(&far_right), None);
+        assert_eq!(
+            Rect::new(f64::MAX, 0.0, 1.0, 1.0).checked_scale(f64::MAX),
+            None
+        );
+        assert_eq!(Rect::checked_new(f64::MAX, 0.0, f64::MAX, 1.0), None);
+    }
+
+    #[test]
+    fn small_and_wide_integer_types() {
+        assert_eq!(Rect::new(0u8, 0, 15, 17).area(), 255);
+        assert_eq!(Rect::new(0u8, 0, 16, 16).checked_area(), None);
+        assert_eq!(Rect::checked_new(i8::MAX, 0, 1, 1), None);
+        assert_eq!(Rect::new(-5i16, 0, 10, 1).right(), 5);
+        assert_eq!(Rect::new(0usize, 0, 3, 4).perimeter(), 14);
+        assert_eq!(Rect::new(0u128, 0, u128::MAX, 1).area(), u128::MAX);
+        assert_eq!(Rect::new(isize::MIN, 0, 1, 1).left(), isize::MIN);
+    }
+
+    #[test]
+    #[should_panic(expected = "Rect area overflowed")]
+    fn rect_area_panics_on_overflow() {
+        Rect::new(0u32, 0, u32::MAX, 2).area();
+    }
+
+    #[test]
+    #[should_panic(expected = "Rect edge overflowed")]
+    fn edges_must_fit() {
+        Rect::new(u32::MAX, 0, 1, 1);
+    }
+
+    #[test]
+    fn intersection_properties() {
+        let mut rng = StdRng::seed_from_u64(5);
+        for _ in 0..CASES {
+            let (a, b) = (random_rect(&mut rng), random_rect(&mut rng));
+            let both = a.intersection(&b);
+            assert_eq!(both, b.intersection(&a));
+            assert_eq!(a.intersects(&b), both.is_some());
+            if let Some(both) = both {
+                assert!(both.area() <= a.area() && both.area() <= b.area());
+                assert!(a.contains(&both) && b.contains(&both));
+                assert!(!both.is_empty());
+            }
+            // A point is in the intersection exactly when it is in both rectangles.
+            let point = random_point(&mut rng);
+            assert_eq!(
+                both.is_some_and(|both| both.contains_point(point)),
+                a.contains_point(point) && b.contains_point(point)
+            );
+        }
+    }
+
+    #[test]
+    fn union_properties() {
+        let mut rng = StdRng::seed_from_u64(6);
+        for _ in 0..CASES {
+            let (a, b) = (random_rect(&mut rng), random_rect(&mut rng));
+            let all = a.union(&b);
+            assert_eq!(all, b.union(&a));
+            assert!(all.contains(&a) && all.contains(&b));
+            assert!(all.area() >= a.area() && all.area() >= b.area());
+            let point = random_point(&mut rng);
+            if a.contains_point(point) || b.contains_point(point) {
+                assert!(all.contains_point(point));
+            }
+        }
+    }
+
+    #[test]
+    fn moving_and_scaling_properties() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..CASES {
+            let a = random_rect(&mut rng);
+            let (dx, dy) = (rng.gen_range(-20..20), rng.gen_range(-20..20));
+            let moved = a.translate(dx, dy);
+            assert_eq!(moved.area(), a.area());
+            assert_eq!(moved.perimeter(), a.perimeter());
+            assert_eq!(moved.translate(-dx, -dy), a);
+
+            let point = random_point(&mut rng);
+            let moved_point = Point {
+                x: point.x + dx,
+                y: point.y + dy,
+            };
+            assert_eq!(a.contains_point(point), moved.contains_point(moved_point));
+
+            let factor = rng.gen_range(0..5);
+            let scaled = a.scale(factor);
+            assert_eq!(scaled.area(), a.area() * factor * factor);
+            assert_eq!(scaled.perimeter(), a.perimeter() * factor);
+        }
+    }
+}
@@ -1,4 +1,7 @@
 // An Example Program Using Structs
+mod geometry;
+
+use geometry::{Point, Rect};
 
 #[derive(Debug)]
 struct Rectangle {
@@ -8,12 +11,37 @@ struct Rectangle {
 
 // Defining Methods
 impl Rectangle {
+    // `width * height` does not always fit in a u32: 70_000 by 70_000 pixels is already
+    // 4.9 billion square pixels. Plain `*` panics on that in debug builds but wraps around
+    // to a wrong answer in release builds, so `area` checks, and panics in both.
     fn area(&self) -> u32 {
-        self.width * self.height
+        self.checked_area()
+            .expect("Rectangle area does not fit in a u32; use area_wide")
+    }
+    // `None` when the area does not fit in a u32.
+    fn checked_area(&self) -> Option<u32> {
+        self.width.checked_mul(self.height)
+    }
+    // u32::MAX when the area does not fit in a u32.
+    fn saturating_area(&self) -> u32 {
+        self.width.saturating_mul(self.height)
+    }
+    // Always right: the product of two u32s always fits in a u64.
+    fn area_wide(&self) -> u64 {
+        u64::from(self.width) * u64::from(self.height)
     }
     fn can_hold(&self, other: &Rectangle) -> bool {
         self.width > other.width && self.height > other.height
     }
+    // Puts the rectangle somewhere: its top-left corner goes at (x, y). Panics if the right
+    // or bottom edge would be past u32::MAX.
+    fn at(&self, x: u32, y: u32) -> Rect<u32> {
+        Rect::new(x, y, self.width, self.height)
+    }
+    // `None` when the right or bottom edge would be past u32::MAX.
+    fn checked_at(&self, x: u32, y: u32) -> Option<Rect<u32>> {
+        Rect::checked_new(x, y, self.width, self.height)
+    }
 }
 
 // Associated Functions:
@@ -58,6 +86,55 @@ fn main() {
 
     println!("Can rect1 hold rect2? {}", rect1.can_hold(&rect2));
     println!("Can rect1 hold rect3? {}", rect1.can_hold(&rect3));
+
+    // Once rectangles have a place as well as a size, we can ask where they overlap.
+    // A rectangle this big has an area that does not fit in a u32.
+    let huge = Rectangle {
+        width: u32::MAX,
+        height: 2,
+    };
+    println!(
+        "The huge rectangle's area: checked {:?}, saturating {}, wide {}",
+        huge.checked_area(),
+        huge.saturating_area(),
+        huge.area_wide()
+    );
+
+    let placed1 = rect1.at(0, 0);
+    let placed3 = rect3.at(20, 10);
+    let overlap = placed1.intersection(&placed3);
+    println!(
+        "Do rect1 at (0, 0) and rect3 at (20, 10) overlap? {}, in {overlap:?}, covering {:?} square pixels",
+        placed1.intersects(&placed3),
+        overlap.map(|overlap| overlap.area())
+    );
+    println!(
+        "rect1 at (u32::MAX, 0) would stick out past the largest u32: {:?}",
+        rect1.checked_at(u32::MAX, 0)
+    );
+    let both = placed1.union(&placed3);
+    println!("Their bounding box is {both:?}");
+    println!(
+        "Is (25, 45) inside rect1? {}",
+        placed1.contains_point(Point::new(25, 45))
+    );
+    let dragged = Rect::from_corners(Point::new(30, 50), Point::new(0, 0));
+    println!(
+        "Dragging from (30, 50) to (0, 0) selects {dragged:?}, the same as rect1: {}",
+        dragged == placed1
+    );
+    let moved = placed1.translate(100, 0).scale(2);
+    let corner = moved.origin();
+    println!(
+        "rect1 moved right by 100 and doubled: {} by {} at ({}, {}), perimeter {}, still inside the box: {}, empty: {}",
+        moved.width(),
+        moved.height(),
+        corner.x(),
+        corner.y(),
+        moved.perimeter(),
+        both.contains(&moved),
+        moved.is_empty()
+    );
 }
 
 // we want to borrow the struct rather than take ownership of it.
@@ -65,5 +142,79 @@ fn main() {
 fn area(rect: &Rectangle) -> u32 {
     // note that accessing fields of a borrowed struct instance does not move the field values,
     // which is why you often see borrows of structs
-    rect.width * rect.height
+    rect.width
+        .checked_mul(rect.height)
+        .expect("Rectangle area does not fit in a u32")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn area_at_the_u32_limits() {
+        let fits = Rectangle {
+            width: u32::MAX,
+            height: 1,
+        };
+        assert_eq!(fits.area(), u32::MAX);
+        assert_eq!(fits.checked_area(), Some(u32::MAX));
+        assert_eq!(area(&fits), u32::MAX);
+
+        let largest = Rectangle {
+            width: u32::MAX,
+            height: u32::MAX,
+        };
+        assert_eq!(largest.checked_area(), None);
+        assert_eq!(largest.saturating_area(), u32::MAX);
+        assert_eq!(largest.area_wide(), 18_446_744_065_119_617_025);
+
+        let just_over = Rectangle {
+            width: 65_536,
+            height: 65_536,
+        };
+        assert_eq!(just_over.checked_area(), None);
+        assert_eq!(just_over.area_wide(), 1 << 32);
+    }
+
+    #[test]
+    fn placing_at_the_u32_limits() {
+        let rect = Rectangle {
+            width: 1,
+            height: 1,
+        };
+        let last = u32::MAX - 1;
+        assert_eq!(rect.checked_at(last, last), Some(rect.at(last, last)));
+        assert_eq!(rect.checked_at(u32::MAX, 0), None);
+        assert_eq!(rect.checked_at(0, u32::MAX), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Rect edge overflowed")]
+    fn at_panics_past_the_u32_limits() {
+        Rectangle {
+            width: 1,
+            height: 1,
+        }
+        .at(u32::MAX, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "does not fit in a u32")]
+    fn area_panics_instead_of_wrapping() {
+        Rectangle {
+            width: u32::MAX,
+            height: 2,
+        }
+        .area();
+    }
+
+    #[test]
+    #[should_panic(expected = "does not fit in a u32")]
+    fn free_area_panics_too() {
+        area(&Rectangle {
+            width: 1 << 16,
+            height: 1 << 16,
+        });
+    }
 }